@@ -0,0 +1,349 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Field, Fields, GenericArgument, Ident, LitInt,
+    LitStr, PathArguments, Type,
+};
+
+#[derive(Default)]
+struct EgglogAttributes {
+    name: Option<String>,
+    sort: Option<String>,
+    cost: Option<usize>,
+}
+
+// `allowed` lists the attributes that mean something where they are placed:
+// `name` and `cost` on datatypes and variants, `sort` on fields.
+fn egglog_attributes(attrs: &[Attribute], allowed: &[&str]) -> syn::Result<EgglogAttributes> {
+    let mut egglog_attributes = EgglogAttributes::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("egglog")) {
+        attr.parse_nested_meta(|meta| {
+            if let Some(ident) = meta.path.get_ident() {
+                let ident = ident.to_string();
+                if ["name", "sort", "cost"].contains(&ident.as_str())
+                    && !allowed.contains(&ident.as_str())
+                {
+                    return Err(meta.error(format!(
+                        "egglog attribute `{}` is not supported here, expected one of: {}",
+                        ident,
+                        allowed.join(", ")
+                    )));
+                }
+            }
+            if meta.path.is_ident("name") {
+                egglog_attributes.name = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("sort") {
+                egglog_attributes.sort = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("cost") {
+                egglog_attributes.cost = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+            } else {
+                return Err(meta.error("unsupported egglog attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(egglog_attributes)
+}
+
+fn field_sort(ty: &Type) -> syn::Result<String> {
+    match ty {
+        Type::Path(type_path) => {
+            let segment = type_path
+                .path
+                .segments
+                .last()
+                .ok_or_else(|| syn::Error::new_spanned(ty, "expected a sort type"))?;
+            match (segment.ident.to_string().as_str(), &segment.arguments) {
+                ("Box" | "Rc" | "Arc", PathArguments::AngleBracketed(arguments)) => {
+                    match arguments.args.first() {
+                        Some(GenericArgument::Type(inner)) => field_sort(inner),
+                        _ => Err(syn::Error::new_spanned(ty, "expected a boxed sort type")),
+                    }
+                }
+                // egglog types integer literals as i64, unsigned fields included.
+                (
+                    "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize",
+                    _,
+                ) => Ok("i64".to_owned()),
+                ("f32" | "f64", _) => Ok("f64".to_owned()),
+                ("String" | "str", _) => Ok("String".to_owned()),
+                ("bool", _) => Ok("bool".to_owned()),
+                (sort, PathArguments::None) => Ok(sort.to_owned()),
+                _ => Err(syn::Error::new_spanned(
+                    ty,
+                    "generic field types need #[egglog(sort = \"...\")]",
+                )),
+            }
+        }
+        Type::Tuple(tuple) if tuple.elems.is_empty() => Ok("Unit".to_owned()),
+        _ => Err(syn::Error::new_spanned(
+            ty,
+            "unsupported field type, use #[egglog(sort = \"...\")]",
+        )),
+    }
+}
+
+struct Constructor<'a> {
+    path: TokenStream2,
+    name: String,
+    cost: Option<usize>,
+    fields: &'a Fields,
+    field_sorts: Vec<Option<String>>,
+}
+
+impl Constructor<'_> {
+    fn field_list(&self) -> Vec<&Field> {
+        match self.fields {
+            Fields::Named(fields) => fields.named.iter().collect(),
+            Fields::Unnamed(fields) => fields.unnamed.iter().collect(),
+            Fields::Unit => vec![],
+        }
+    }
+
+    fn bindings(&self) -> Vec<Ident> {
+        (0..self.field_list().len())
+            .map(|index| format_ident!("__egglog_field{}", index))
+            .collect()
+    }
+
+    fn construct(&self, values: &[TokenStream2]) -> TokenStream2 {
+        let path = &self.path;
+        match self.fields {
+            Fields::Named(fields) => {
+                let names = fields.named.iter().map(|field| &field.ident);
+                quote!(#path { #(#names: #values),* })
+            }
+            Fields::Unnamed(_) => quote!(#path(#(#values),*)),
+            Fields::Unit => quote!(#path),
+        }
+    }
+
+    fn pattern(&self) -> TokenStream2 {
+        let bindings = self
+            .bindings()
+            .into_iter()
+            .map(|binding| quote!(#binding))
+            .collect::<Vec<_>>();
+        self.construct(&bindings)
+    }
+}
+
+fn field_sorts(fields: &Fields) -> syn::Result<Vec<Option<String>>> {
+    fields
+        .iter()
+        .map(|field| Ok(egglog_attributes(&field.attrs, &["sort"])?.sort))
+        .collect()
+}
+
+fn constructors(input: &DeriveInput) -> syn::Result<(String, Vec<Constructor<'_>>)> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "egglog derives are not supported for generic types",
+        ));
+    }
+    let datatype_allowed: &[&str] = match &input.data {
+        Data::Struct(_) => &["name", "cost"],
+        _ => &["name"],
+    };
+    let datatype_attributes = egglog_attributes(&input.attrs, datatype_allowed)?;
+    let datatype_name = datatype_attributes
+        .name
+        .unwrap_or_else(|| input.ident.to_string());
+    let constructors = match &input.data {
+        Data::Enum(data_enum) => data_enum
+            .variants
+            .iter()
+            .map(|variant| {
+                let variant_attributes = egglog_attributes(&variant.attrs, &["name", "cost"])?;
+                let ident = &variant.ident;
+                Ok(Constructor {
+                    path: quote!(Self::#ident),
+                    name: variant_attributes
+                        .name
+                        .unwrap_or_else(|| variant.ident.to_string()),
+                    cost: variant_attributes.cost,
+                    fields: &variant.fields,
+                    field_sorts: field_sorts(&variant.fields)?,
+                })
+            })
+            .collect::<syn::Result<Vec<_>>>()?,
+        Data::Struct(data_struct) => vec![Constructor {
+            path: quote!(Self),
+            name: datatype_name.clone(),
+            cost: datatype_attributes.cost,
+            fields: &data_struct.fields,
+            field_sorts: field_sorts(&data_struct.fields)?,
+        }],
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "egglog derives are not supported for unions",
+            ))
+        }
+    };
+    Ok((datatype_name, constructors))
+}
+
+fn expand_egglog_datatype(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let (datatype_name, constructors) = constructors(&input)?;
+
+    let mut variants = vec![];
+    for constructor in constructors.iter() {
+        let mut types = vec![];
+        for (field, sort) in constructor
+            .field_list()
+            .into_iter()
+            .zip(constructor.field_sorts.iter())
+        {
+            types.push(match sort {
+                Some(sort) => sort.clone(),
+                None => field_sort(&field.ty)?,
+            });
+        }
+        let name = &constructor.name;
+        let cost = match constructor.cost {
+            Some(cost) => quote!(Some(#cost)),
+            None => quote!(None),
+        };
+        variants.push(quote! {
+            ::egglog_program::egglog::ast::Variant {
+                span: ::egglog_program::DUMMY_SPAN.clone(),
+                name: ::egglog_program::egglog::ast::Symbol::from(#name),
+                types: vec![#(::egglog_program::egglog::ast::Symbol::from(#types)),*],
+                cost: #cost,
+            }
+        });
+    }
+
+    let ident = &input.ident;
+    Ok(quote! {
+        impl ::egglog_program::EgglogDatatype for #ident {
+            fn egglog_sort_name() -> ::egglog_program::egglog::ast::Symbol {
+                ::egglog_program::egglog::ast::Symbol::from(#datatype_name)
+            }
+
+            fn egglog_datatype() -> ::egglog_program::egglog::ast::Command {
+                ::egglog_program::egglog::ast::Command::Datatype {
+                    span: ::egglog_program::DUMMY_SPAN.clone(),
+                    name: <Self as ::egglog_program::EgglogDatatype>::egglog_sort_name(),
+                    variants: vec![#(#variants),*],
+                }
+            }
+        }
+    })
+}
+
+fn expand_to_egglog_expr(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let (_datatype_name, constructors) = constructors(&input)?;
+
+    let arms = constructors.iter().map(|constructor| {
+        let pattern = constructor.pattern();
+        let name = &constructor.name;
+        let bindings = constructor.bindings();
+        quote! {
+            #pattern => Ok(::egglog_program::egglog::ast::GenericExpr::Call(
+                ::egglog_program::DUMMY_SPAN.clone(),
+                ::egglog_program::egglog::ast::Symbol::from(#name),
+                vec![#(::egglog_program::ToEgglogExpr::try_to_egglog_expr(#bindings)?),*],
+            ))
+        }
+    });
+
+    let ident = &input.ident;
+    Ok(quote! {
+        impl ::egglog_program::ToEgglogExpr for #ident {
+            fn try_to_egglog_expr(
+                &self,
+            ) -> ::std::result::Result<
+                ::egglog_program::egglog::ast::Expr,
+                ::egglog_program::EgglogProgramError,
+            > {
+                match self {
+                    #(#arms,)*
+                }
+            }
+        }
+    })
+}
+
+fn expand_from_egglog_term(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let (datatype_name, constructors) = constructors(&input)?;
+
+    let arms = constructors.iter().map(|constructor| {
+        let name = &constructor.name;
+        let arity = constructor.field_list().len();
+        let values = (0..arity)
+            .map(|index| {
+                quote! {
+                    ::egglog_program::FromEgglogTerm::from_egglog_term(
+                        termdag,
+                        &termdag.get(children[#index]),
+                    )?
+                }
+            })
+            .collect::<Vec<_>>();
+        let value = constructor.construct(&values);
+        quote! {
+            #name => {
+                if children.len() != #arity {
+                    return Err(::egglog_program::TermDecodeError::ArityMismatch {
+                        constructor: *constructor,
+                        expected: #arity,
+                        actual: children.len(),
+                    });
+                }
+                Ok(#value)
+            }
+        }
+    });
+
+    let ident = &input.ident;
+    Ok(quote! {
+        impl ::egglog_program::FromEgglogTerm for #ident {
+            fn from_egglog_term(
+                termdag: &::egglog_program::egglog::TermDag,
+                term: &::egglog_program::egglog::Term,
+            ) -> ::std::result::Result<Self, ::egglog_program::TermDecodeError> {
+                let ::egglog_program::egglog::Term::App(constructor, children) = term else {
+                    return Err(::egglog_program::TermDecodeError::UnexpectedTerm {
+                        sort: ::egglog_program::egglog::ast::Symbol::from(#datatype_name),
+                        term: termdag.to_string(term),
+                    });
+                };
+                match constructor.as_str() {
+                    #(#arms)*
+                    _ => Err(::egglog_program::TermDecodeError::UnknownConstructor {
+                        sort: ::egglog_program::egglog::ast::Symbol::from(#datatype_name),
+                        constructor: *constructor,
+                    }),
+                }
+            }
+        }
+    })
+}
+
+#[proc_macro_derive(EgglogDatatype, attributes(egglog))]
+pub fn derive_egglog_datatype(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_egglog_datatype(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(ToEgglogExpr, attributes(egglog))]
+pub fn derive_to_egglog_expr(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_to_egglog_expr(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(FromEgglogTerm, attributes(egglog))]
+pub fn derive_from_egglog_term(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_from_egglog_term(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -1,42 +1,104 @@
-use crate::facts::{get_fact_symbol, EgglogFacts};
-use crate::rules::EgglogRules;
-use crate::schedule::EgglogSchedules;
-use crate::sorts::{get_sort_symbol, EgglogSorts};
+use crate::combine::extend_datatypes;
+use crate::facts::{get_fact_symbol, try_get_fact_symbol, EgglogFacts};
+use crate::parse::{parse_egglog_file, parse_egglog_str};
+use crate::pretty::pretty_egglog_command;
+use crate::queries::{is_query_command, EgglogQueries};
+use crate::rules::{is_rule_command, EgglogRules};
+use crate::schedule::{is_schedule_command, EgglogSchedules};
+use crate::sorts::{get_sort_symbol, is_sort_command, EgglogSorts};
 use crate::EgglogProgramFacts;
 use crate::EgglogProgramSorts;
+use crate::EgglogQueryList;
 use crate::EgglogRuleList;
 use crate::EgglogScheduleList;
+use crate::DUMMY_SPAN;
+use crate::{BuildError, EgglogProgramError};
 use crate::{EgglogCommandList, EgglogSymbols};
 use derive_getters::Getters;
-use egglog::ast::Symbol;
+use egglog::ast::{Command, Expr, Symbol};
 use frunk::monoid::Monoid;
 use frunk::semigroup::Semigroup;
 use itertools::Itertools;
 use state_shift::{impl_state, type_state};
 use std::fmt::Display;
 use std::ops::Add;
+use std::path::Path;
+
+pub(crate) fn sort_symbols(sorts: &EgglogSorts) -> EgglogSymbols {
+    let sort_symbol_lists: Vec<Vec<Symbol>> = sorts.iter().map(get_sort_symbol).collect_vec();
+    sort_symbol_lists.into_iter().flatten().collect()
+}
+
+pub(crate) fn fact_symbols(facts: &EgglogFacts) -> EgglogSymbols {
+    let fact_symbol_lists: Vec<Symbol> = facts.iter().map(get_fact_symbol).collect_vec();
+    fact_symbol_lists.into_iter().collect()
+}
+
+pub(crate) fn try_merge_facts(
+    program_facts: Option<EgglogProgramFacts>,
+    facts: EgglogFacts,
+) -> Result<EgglogProgramFacts, BuildError> {
+    let (mut merged_symbols, merged_facts) = program_facts.unwrap_or_default();
+    for fact in facts.iter() {
+        let symbol = get_fact_symbol(fact);
+        if !merged_symbols.insert(symbol) {
+            return Err(BuildError::DuplicateFact { symbol });
+        }
+    }
+    Ok((merged_symbols, merged_facts.add_facts(facts)))
+}
+
+// The typestate builder keeps the first failure and reports it from its
+// terminals, like `DynamicProgramBuilder::finish`.
+pub(crate) fn merge_facts(
+    program_facts: Option<EgglogProgramFacts>,
+    facts: EgglogFacts,
+    error: Option<BuildError>,
+) -> (Option<EgglogProgramFacts>, Option<BuildError>) {
+    if error.is_some() {
+        return (program_facts, error);
+    }
+    match try_merge_facts(program_facts.clone(), facts) {
+        Ok(merged_facts) => (Some(merged_facts), None),
+        Err(error) => (program_facts, Some(error)),
+    }
+}
+
+pub(crate) fn append_section<Section>(
+    sections: Option<Vec<Section>>,
+    section: Section,
+) -> Option<Vec<Section>> {
+    let mut appended_sections = sections.unwrap_or_default();
+    appended_sections.push(section);
+    Some(appended_sections)
+}
 
 #[derive(Debug, Clone, Default, Getters)]
 pub struct EgglogProgram {
-    sorts: EgglogProgramSorts,
-    facts: EgglogProgramFacts,
-    rules: EgglogRuleList,
-    bindings: EgglogSymbols,
-    schedules: EgglogScheduleList,
+    pub(crate) sorts: EgglogProgramSorts,
+    pub(crate) facts: EgglogProgramFacts,
+    pub(crate) rules: EgglogRuleList,
+    pub(crate) bindings: EgglogSymbols,
+    pub(crate) schedules: EgglogScheduleList,
+    pub(crate) queries: EgglogQueryList,
+    pub(crate) binding_extraction: Option<usize>,
 }
 
-#[type_state(states = (Initial, DefinedSorts, FactsPresent, LoadedRules, BoundSymbols, AddedSchedules), slots = (Initial, Initial, Initial))]
+#[type_state(states = (Initial, DefinedSorts, FactsPresent, LoadedRules, BoundSymbols, AddedSchedules, AddedQueries), slots = (Initial, Initial, Initial, Initial))]
 pub struct EgglogProgramBuilder {
     sorts: Option<EgglogProgramSorts>,
     facts: Option<EgglogProgramFacts>,
     rules: Option<EgglogRuleList>,
     bindings: Option<EgglogSymbols>,
     schedules: Option<EgglogScheduleList>,
+    queries: Option<EgglogQueryList>,
+    binding_extraction: Option<usize>,
+    error: Option<BuildError>,
 }
 
 #[impl_state]
 impl EgglogProgramBuilder {
-    #[require(Initial, Initial, Initial)] // require the default state for the constructor
+    #[require(Initial, Initial, Initial, Initial)] // require the default state for the constructor
     pub fn initialize() -> Self {
         EgglogProgramBuilder {
             sorts: None,
@@ -44,51 +106,122 @@ impl EgglogProgramBuilder {
             rules: None,
             bindings: None,
             schedules: None,
+            queries: None,
+            binding_extraction: None,
+            error: None,
         }
     }
 
-    #[require(Initial, F, R)] // can be called only at `Initial` state.
-    #[switch_to(DefinedSorts, F, R)] // Transitions to `DefinedSorts` state
+    #[require(Initial, F, R, Q)] // can be called only at `Initial` state.
+    #[switch_to(DefinedSorts, F, R, Q)] // Transitions to `DefinedSorts` state
     pub fn sorts(self, sorts: EgglogSorts) -> EgglogProgramBuilder {
-        let sort_symbol_lists: Vec<Vec<Symbol>> = sorts.iter().map(get_sort_symbol).collect_vec();
-        let sort_symbols: EgglogSymbols = sort_symbol_lists.into_iter().flatten().collect();
         EgglogProgramBuilder {
-            sorts: Some((sort_symbols, sorts)),
+            sorts: Some((sort_symbols(&sorts), sorts)),
             facts: None,
             rules: None,
             bindings: None,
             schedules: None,
+            queries: None,
+            binding_extraction: None,
+            error: None,
         }
     }
 
-    #[require(DefinedSorts, F, R)]
-    #[switch_to(DefinedSorts, FactsPresent, R)]
+    #[require(DefinedSorts, Initial, R, Q)]
+    #[switch_to(DefinedSorts, FactsPresent, R, Q)]
     pub fn facts(self, facts: EgglogFacts) -> EgglogProgramBuilder {
-        let fact_symbol_lists: Vec<Symbol> = facts.iter().map(get_fact_symbol).collect_vec();
-        let fact_symbols: EgglogSymbols = fact_symbol_lists.into_iter().collect();
+        let (facts, error) = merge_facts(self.facts, facts, self.error);
         EgglogProgramBuilder {
             sorts: self.sorts,
-            facts: Some((fact_symbols, facts)),
+            facts,
             rules: self.rules,
-            bindings: None,
+            bindings: self.bindings,
+            schedules: self.schedules,
+            queries: self.queries,
+            binding_extraction: self.binding_extraction,
+            error,
+        }
+    }
+
+    #[require(DefinedSorts, FactsPresent, R, Q)]
+    #[switch_to(DefinedSorts, FactsPresent, R, Q)]
+    pub fn facts(self, facts: EgglogFacts) -> EgglogProgramBuilder {
+        let (facts, error) = merge_facts(self.facts, facts, self.error);
+        EgglogProgramBuilder {
+            sorts: self.sorts,
+            facts,
+            rules: self.rules,
+            bindings: self.bindings,
             schedules: self.schedules,
+            queries: self.queries,
+            binding_extraction: self.binding_extraction,
+            error,
         }
     }
 
-    #[require(DefinedSorts, F, R)]
-    #[switch_to(DefinedSorts, F, LoadedRules)]
+    #[require(DefinedSorts, BoundSymbols, R, Q)]
+    #[switch_to(DefinedSorts, BoundSymbols, R, Q)]
+    pub fn facts(self, facts: EgglogFacts) -> EgglogProgramBuilder {
+        let (facts, error) = merge_facts(self.facts, facts, self.error);
+        EgglogProgramBuilder {
+            sorts: self.sorts,
+            facts,
+            rules: self.rules,
+            bindings: self.bindings,
+            schedules: self.schedules,
+            queries: self.queries,
+            binding_extraction: self.binding_extraction,
+            error,
+        }
+    }
+
+    #[require(DefinedSorts, F, Initial, Q)]
+    #[switch_to(DefinedSorts, F, LoadedRules, Q)]
     pub fn rules(self, rules: EgglogRules) -> EgglogProgramBuilder {
         EgglogProgramBuilder {
             sorts: self.sorts,
             facts: self.facts,
-            rules: Some(vec![rules]),
+            rules: append_section(self.rules, rules),
             bindings: self.bindings,
-            schedules: None,
+            schedules: self.schedules,
+            queries: self.queries,
+            binding_extraction: self.binding_extraction,
+            error: self.error,
+        }
+    }
+
+    #[require(DefinedSorts, F, LoadedRules, Q)]
+    #[switch_to(DefinedSorts, F, LoadedRules, Q)]
+    pub fn rules(self, rules: EgglogRules) -> EgglogProgramBuilder {
+        EgglogProgramBuilder {
+            sorts: self.sorts,
+            facts: self.facts,
+            rules: append_section(self.rules, rules),
+            bindings: self.bindings,
+            schedules: self.schedules,
+            queries: self.queries,
+            binding_extraction: self.binding_extraction,
+            error: self.error,
+        }
+    }
+
+    #[require(DefinedSorts, F, AddedSchedules, Q)]
+    #[switch_to(DefinedSorts, F, AddedSchedules, Q)]
+    pub fn rules(self, rules: EgglogRules) -> EgglogProgramBuilder {
+        EgglogProgramBuilder {
+            sorts: self.sorts,
+            facts: self.facts,
+            rules: append_section(self.rules, rules),
+            bindings: self.bindings,
+            schedules: self.schedules,
+            queries: self.queries,
+            binding_extraction: self.binding_extraction,
+            error: self.error,
         }
     }
 
-    #[require(DefinedSorts, FactsPresent, R)]
-    #[switch_to(DefinedSorts, BoundSymbols, R)]
+    #[require(DefinedSorts, FactsPresent, R, Q)]
+    #[switch_to(DefinedSorts, BoundSymbols, R, Q)]
     pub fn bindings(self, bindings: EgglogSymbols) -> EgglogProgramBuilder {
         EgglogProgramBuilder {
             sorts: self.sorts,
@@ -96,73 +229,377 @@ impl EgglogProgramBuilder {
             rules: self.rules,
             bindings: Some(bindings),
             schedules: self.schedules,
+            queries: self.queries,
+            binding_extraction: self.binding_extraction,
+            error: self.error,
         }
     }
 
-    #[require(DefinedSorts, F, LoadedRules)]
-    #[switch_to(DefinedSorts, F, AddedSchedules)]
+    #[require(DefinedSorts, BoundSymbols, R, Q)]
+    #[switch_to(DefinedSorts, BoundSymbols, R, Q)]
+    pub fn extract_bindings(self, variants: usize) -> EgglogProgramBuilder {
+        EgglogProgramBuilder {
+            sorts: self.sorts,
+            facts: self.facts,
+            rules: self.rules,
+            bindings: self.bindings,
+            schedules: self.schedules,
+            queries: self.queries,
+            binding_extraction: Some(variants),
+            error: self.error,
+        }
+    }
+
+    #[require(DefinedSorts, F, LoadedRules, Q)]
+    #[switch_to(DefinedSorts, F, AddedSchedules, Q)]
+    pub fn schedules(self, schedules: EgglogSchedules) -> EgglogProgramBuilder {
+        EgglogProgramBuilder {
+            sorts: self.sorts,
+            facts: self.facts,
+            rules: self.rules,
+            bindings: self.bindings,
+            schedules: append_section(self.schedules, schedules),
+            queries: self.queries,
+            binding_extraction: self.binding_extraction,
+            error: self.error,
+        }
+    }
+
+    #[require(DefinedSorts, F, AddedSchedules, Q)]
+    #[switch_to(DefinedSorts, F, AddedSchedules, Q)]
     pub fn schedules(self, schedules: EgglogSchedules) -> EgglogProgramBuilder {
         EgglogProgramBuilder {
             sorts: self.sorts,
             facts: self.facts,
             rules: self.rules,
             bindings: self.bindings,
-            schedules: Some(vec![schedules]),
+            schedules: append_section(self.schedules, schedules),
+            queries: self.queries,
+            binding_extraction: self.binding_extraction,
+            error: self.error,
         }
     }
 
-    #[require(DefinedSorts, F, R)]
+    #[require(DefinedSorts, F, R, Q)]
+    #[switch_to(DefinedSorts, F, R, AddedQueries)]
+    pub fn queries(self, queries: EgglogQueries) -> EgglogProgramBuilder {
+        EgglogProgramBuilder {
+            sorts: self.sorts,
+            facts: self.facts,
+            rules: self.rules,
+            bindings: self.bindings,
+            schedules: self.schedules,
+            queries: append_section(self.queries, queries),
+            binding_extraction: self.binding_extraction,
+            error: self.error,
+        }
+    }
+
+    #[require(DefinedSorts, F, R, Q)]
     pub fn definitions(self) -> EgglogProgram {
+        if let Some(error) = self.error {
+            panic!("Failure to build program: {}", error);
+        }
         EgglogProgram {
             sorts: self.sorts.expect("Sorts Guaranteed at compile-time."),
             facts: self.facts.unwrap_or_default(),
             rules: self.rules.unwrap_or_default(),
             bindings: self.bindings.unwrap_or_default(),
             schedules: self.schedules.unwrap_or_default(),
+            queries: self.queries.unwrap_or_default(),
+            binding_extraction: self.binding_extraction,
+        }
+    }
+
+    #[require(DefinedSorts, F, R, Q)]
+    pub fn try_definitions(self) -> Result<EgglogProgram, EgglogProgramError> {
+        if let Some(error) = self.error {
+            return Err(error.into());
         }
+        let egglog_program = EgglogProgram {
+            sorts: self.sorts.expect("Sorts Guaranteed at compile-time."),
+            facts: self.facts.unwrap_or_default(),
+            rules: self.rules.unwrap_or_default(),
+            bindings: self.bindings.unwrap_or_default(),
+            schedules: self.schedules.unwrap_or_default(),
+            queries: self.queries.unwrap_or_default(),
+            binding_extraction: self.binding_extraction,
+        };
+        egglog_program.validate()?;
+        Ok(egglog_program)
     }
 
-    #[require(DefinedSorts, BoundSymbols, R)]
+    #[require(DefinedSorts, BoundSymbols, R, Q)]
     pub fn variables(self) -> EgglogProgram {
+        if let Some(error) = self.error {
+            panic!("Failure to build program: {}", error);
+        }
         EgglogProgram {
             sorts: self.sorts.expect("Sorts Guaranteed at compile-time."),
             facts: self.facts.unwrap_or_default(),
             rules: self.rules.unwrap_or_default(),
             bindings: self.bindings.unwrap_or_default(),
             schedules: self.schedules.unwrap_or_default(),
+            queries: self.queries.unwrap_or_default(),
+            binding_extraction: self.binding_extraction,
+        }
+    }
+
+    #[require(DefinedSorts, BoundSymbols, R, Q)]
+    pub fn try_variables(self) -> Result<EgglogProgram, EgglogProgramError> {
+        if let Some(error) = self.error {
+            return Err(error.into());
         }
+        let egglog_program = EgglogProgram {
+            sorts: self.sorts.expect("Sorts Guaranteed at compile-time."),
+            facts: self.facts.unwrap_or_default(),
+            rules: self.rules.unwrap_or_default(),
+            bindings: self.bindings.unwrap_or_default(),
+            schedules: self.schedules.unwrap_or_default(),
+            queries: self.queries.unwrap_or_default(),
+            binding_extraction: self.binding_extraction,
+        };
+        egglog_program.validate_bindings()?;
+        egglog_program.validate()?;
+        Ok(egglog_program)
     }
 
-    #[require(DefinedSorts, F, AddedSchedules)]
+    #[require(DefinedSorts, F, AddedSchedules, Q)]
     pub fn rewrite(self) -> EgglogProgram {
+        if let Some(error) = self.error {
+            panic!("Failure to build program: {}", error);
+        }
         EgglogProgram {
             sorts: self.sorts.expect("Sorts Guaranteed at compile-time."),
             facts: self.facts.unwrap_or_default(),
             rules: self.rules.unwrap_or_default(),
             bindings: self.bindings.unwrap_or_default(),
             schedules: self.schedules.unwrap_or_default(),
+            queries: self.queries.unwrap_or_default(),
+            binding_extraction: self.binding_extraction,
         }
     }
 
-    #[require(DefinedSorts, BoundSymbols, AddedSchedules)]
+    #[require(DefinedSorts, F, AddedSchedules, Q)]
+    pub fn try_rewrite(self) -> Result<EgglogProgram, EgglogProgramError> {
+        if let Some(error) = self.error {
+            return Err(error.into());
+        }
+        let egglog_program = EgglogProgram {
+            sorts: self.sorts.expect("Sorts Guaranteed at compile-time."),
+            facts: self.facts.unwrap_or_default(),
+            rules: self.rules.unwrap_or_default(),
+            bindings: self.bindings.unwrap_or_default(),
+            schedules: self.schedules.unwrap_or_default(),
+            queries: self.queries.unwrap_or_default(),
+            binding_extraction: self.binding_extraction,
+        };
+        egglog_program.validate()?;
+        Ok(egglog_program)
+    }
+
+    #[require(DefinedSorts, BoundSymbols, AddedSchedules, Q)]
     pub fn program(self) -> EgglogProgram {
+        if let Some(error) = self.error {
+            panic!("Failure to build program: {}", error);
+        }
         EgglogProgram {
             sorts: self.sorts.expect("Sorts Guaranteed at compile-time."),
             facts: self.facts.unwrap_or_default(),
             rules: self.rules.unwrap_or_default(),
             bindings: self.bindings.unwrap_or_default(),
             schedules: self.schedules.unwrap_or_default(),
+            queries: self.queries.unwrap_or_default(),
+            binding_extraction: self.binding_extraction,
         }
     }
+
+    #[require(DefinedSorts, BoundSymbols, AddedSchedules, Q)]
+    pub fn try_program(self) -> Result<EgglogProgram, EgglogProgramError> {
+        if let Some(error) = self.error {
+            return Err(error.into());
+        }
+        let egglog_program = EgglogProgram {
+            sorts: self.sorts.expect("Sorts Guaranteed at compile-time."),
+            facts: self.facts.unwrap_or_default(),
+            rules: self.rules.unwrap_or_default(),
+            bindings: self.bindings.unwrap_or_default(),
+            schedules: self.schedules.unwrap_or_default(),
+            queries: self.queries.unwrap_or_default(),
+            binding_extraction: self.binding_extraction,
+        };
+        egglog_program.validate_bindings()?;
+        egglog_program.validate()?;
+        Ok(egglog_program)
+    }
+}
+
+impl EgglogProgram {
+    pub fn validate_bindings(&self) -> Result<(), EgglogProgramError> {
+        let unbound_symbols = self
+            .bindings
+            .iter()
+            .filter(|binding| !self.facts.0.contains(*binding))
+            .copied()
+            .collect_vec();
+        if unbound_symbols.is_empty() {
+            Ok(())
+        } else {
+            Err(EgglogProgramError::UnboundBindings {
+                symbols: unbound_symbols,
+            })
+        }
+    }
+
+    fn binding_extractions(&self) -> EgglogCommandList {
+        match self.binding_extraction {
+            Some(variants) => self
+                .bindings
+                .iter()
+                .map(|binding| Command::QueryExtract {
+                    span: DUMMY_SPAN.clone(),
+                    variants,
+                    expr: Expr::Var(DUMMY_SPAN.clone(), *binding),
+                })
+                .collect_vec(),
+            None => EgglogCommandList::default(),
+        }
+    }
+
+    pub fn from_egglog_str(
+        program_str: &str,
+    ) -> Result<(Self, EgglogCommandList), EgglogProgramError> {
+        Ok(Self::from_commands(parse_egglog_str(None, program_str)?))
+    }
+
+    pub fn from_egglog_file<P: AsRef<Path>>(
+        program_path: P,
+    ) -> Result<(Self, EgglogCommandList), EgglogProgramError> {
+        Ok(Self::from_commands(parse_egglog_file(program_path)?))
+    }
+
+    pub fn to_egglog_source(&self) -> String {
+        let program_sections: Vec<(&str, EgglogCommandList)> = vec![
+            ("Sorts", self.sorts.1.dependency_ordered().to_vec()),
+            ("Facts", self.facts.1.to_vec()),
+            (
+                "Rules",
+                self.rules
+                    .iter()
+                    .flat_map(|rules| rules.to_vec())
+                    .collect_vec(),
+            ),
+            (
+                "Schedules",
+                self.schedules
+                    .iter()
+                    .flat_map(|schedules| schedules.to_vec())
+                    .collect_vec(),
+            ),
+            (
+                "Queries",
+                self.queries
+                    .iter()
+                    .flat_map(|queries| queries.to_vec())
+                    .collect_vec(),
+            ),
+            ("Bindings", self.binding_extractions()),
+        ];
+        program_sections
+            .into_iter()
+            .filter(|(_section_name, commands)| !commands.is_empty())
+            .map(|(section_name, commands)| {
+                format!(
+                    ";; {}\n{}",
+                    section_name,
+                    commands.iter().map(pretty_egglog_command).join("\n")
+                )
+            })
+            .join("\n\n")
+    }
+
+    /// Sections are emitted as sorts, facts, rules, schedules, queries. A fact,
+    /// rule or schedule following a schedule or query would change meaning if
+    /// moved in front of it, so it and every later command are left unrouted.
+    pub fn from_commands<CommandList>(command_list: CommandList) -> (Self, EgglogCommandList)
+    where
+        CommandList: IntoIterator<Item = Command>,
+    {
+        let mut sort_commands = EgglogCommandList::default();
+        let mut fact_commands = EgglogCommandList::default();
+        let mut rule_commands = EgglogCommandList::default();
+        let mut schedule_commands = EgglogCommandList::default();
+        let mut query_commands = EgglogCommandList::default();
+        let mut unrouted_commands = EgglogCommandList::default();
+        let mut ran_schedules = false;
+        let mut ran_queries = false;
+        let mut command_list = command_list.into_iter();
+        for command in command_list.by_ref() {
+            let reordered = if is_schedule_command(&command) {
+                ran_queries
+            } else if try_get_fact_symbol(&command).is_ok() || is_rule_command(&command) {
+                ran_schedules || ran_queries
+            } else {
+                false
+            };
+            if reordered {
+                unrouted_commands.push(command);
+                break;
+            }
+            if is_sort_command(&command) {
+                sort_commands.push(command);
+            } else if try_get_fact_symbol(&command).is_ok() {
+                fact_commands.push(command);
+            } else if is_rule_command(&command) {
+                rule_commands.push(command);
+            } else if is_schedule_command(&command) {
+                ran_schedules = true;
+                schedule_commands.push(command);
+            } else if is_query_command(&command) {
+                ran_queries = true;
+                query_commands.push(command);
+            } else {
+                unrouted_commands.push(command);
+            }
+        }
+        unrouted_commands.extend(command_list);
+
+        let sorts = EgglogSorts::default().add_sorts(sort_commands);
+        let facts = EgglogFacts::default().add_facts(fact_commands);
+        let rules = EgglogRules::default().add_rules(rule_commands);
+        let schedules = EgglogSchedules::default().add_schedule(schedule_commands);
+        let queries = EgglogQueries::default().add_queries(query_commands);
+        let program = Self {
+            sorts: (sort_symbols(&sorts), sorts),
+            facts: (fact_symbols(&facts), facts),
+            rules: if rules.is_empty() {
+                vec![]
+            } else {
+                vec![rules]
+            },
+            bindings: EgglogSymbols::default(),
+            schedules: if schedules.is_empty() {
+                vec![]
+            } else {
+                vec![schedules]
+            },
+            queries: if queries.is_empty() {
+                vec![]
+            } else {
+                vec![queries]
+            },
+            binding_extraction: None,
+        };
+        (program, unrouted_commands)
+    }
 }
 
 impl Semigroup for EgglogProgram {
     fn combine(&self, program_update: &Self) -> Self {
-        let combined_sorts = self.sorts.1.clone().add_sorts(
-            program_update
-                .sorts
-                .1
-                .clone()
+        let (extended_sorts, incoming_sorts) =
+            extend_datatypes(&self.sorts.1, &program_update.sorts.1);
+        let combined_sorts = extended_sorts.add_sorts(
+            incoming_sorts
                 .into_iter()
                 .filter(|sort| !self.sorts.0.contains(&get_sort_symbol(sort)[0]))
                 .collect_vec(),
@@ -186,6 +623,8 @@ impl Semigroup for EgglogProgram {
         combined_rules.append(&mut program_update.rules.clone());
         let mut combined_schedules = self.schedules.clone();
         combined_schedules.append(&mut program_update.schedules.clone());
+        let mut combined_queries = self.queries.clone();
+        combined_queries.append(&mut program_update.queries.clone());
         let mut combined_bindings = self.bindings.clone();
         combined_bindings.extend(program_update.bindings.clone());
         Self {
@@ -194,6 +633,10 @@ impl Semigroup for EgglogProgram {
             rules: combined_rules,
             schedules: combined_schedules,
             bindings: combined_bindings,
+            queries: combined_queries,
+            binding_extraction: self
+                .binding_extraction
+                .or(program_update.binding_extraction),
         }
     }
 }
@@ -208,10 +651,9 @@ impl Add for EgglogProgram {
     type Output = Self;
 
     fn add(mut self, mut rhs: Self) -> Self::Output {
-        self.sorts.1 = self.sorts.1.add_sorts(
-            rhs.sorts
-                .1
-                .clone()
+        let (extended_sorts, incoming_sorts) = extend_datatypes(&self.sorts.1, &rhs.sorts.1);
+        self.sorts.1 = extended_sorts.add_sorts(
+            incoming_sorts
                 .into_iter()
                 .filter(|sort| !self.sorts.0.contains(&get_sort_symbol(sort)[0]))
                 .collect_vec(),
@@ -228,24 +670,32 @@ impl Add for EgglogProgram {
         self.facts.0.extend(rhs.facts.0);
         self.rules.append(&mut rhs.rules);
         self.schedules.append(&mut rhs.schedules);
+        self.queries.append(&mut rhs.queries);
         self.bindings.extend(rhs.bindings);
+        self.binding_extraction = self.binding_extraction.or(rhs.binding_extraction);
         self
     }
 }
 
 impl From<EgglogProgram> for EgglogCommandList {
     fn from(program: EgglogProgram) -> Self {
+        let binding_extractions = program.binding_extractions();
         program
             .sorts
             .1
+            .dependency_ordered()
             .into_iter()
             .chain(
                 program.facts.1.into_iter().chain(
-                    program
-                        .rules
-                        .into_iter()
-                        .flatten()
-                        .chain(program.schedules.into_iter().flatten()),
+                    program.rules.into_iter().flatten().chain(
+                        program.schedules.into_iter().flatten().chain(
+                            program
+                                .queries
+                                .into_iter()
+                                .flatten()
+                                .chain(binding_extractions),
+                        ),
+                    ),
                 ),
             )
             .collect_vec()
@@ -254,15 +704,14 @@ impl From<EgglogProgram> for EgglogCommandList {
 
 impl Display for EgglogProgram {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let command_list: EgglogCommandList = EgglogCommandList::from(self.clone());
-        write!(f, "EgglogProgram Commands: {:?}", command_list)
+        write!(f, "{}", self.to_egglog_source())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use egglog::ast::{Command, Symbol};
     use egglog::EGraph;
+    use indoc::indoc;
 
     use super::*;
 
@@ -288,6 +737,52 @@ mod tests {
             .program();
     }
 
+    #[test]
+    fn egglog_program_method_order_queries() {
+        let sorts_data = EgglogSorts::default();
+        let rules_data = EgglogRules::default();
+        let schedules_data = EgglogSchedules::default();
+        let queries_data = EgglogQueries::default();
+        let _egglog_program = EgglogProgramBuilder::initialize()
+            .sorts(sorts_data)
+            .rules(rules_data)
+            .queries(queries_data)
+            .schedules(schedules_data)
+            .rewrite();
+    }
+
+    #[test]
+    fn accumulate_queries_after_schedules() {
+        let sorts =
+            EgglogSorts::default().add_sort_str("(datatype Math (Num i64) (Add Math Math))");
+        let egglog_program = EgglogProgramBuilder::initialize()
+            .sorts(sorts)
+            .facts(EgglogFacts::default().add_facts_str("(let expr1 (Num 1))"))
+            .queries(EgglogQueries::default().add_queries_str("(check (= expr1 (Num 1)))"))
+            .rules(EgglogRules::default().add_rule_str("(ruleset math-rules)"))
+            .queries(EgglogQueries::default().add_queries_str("(print-size Num)"))
+            .schedules(
+                EgglogSchedules::default().add_schedule_str("(run-schedule (run math-rules))"),
+            )
+            .rewrite();
+        assert_eq!(
+            2,
+            egglog_program.queries.len(),
+            "Every query section should be kept."
+        );
+        let program_commands: EgglogCommandList = egglog_program.into();
+        let command_strs = program_commands
+            .iter()
+            .map(|command| command.to_string())
+            .collect_vec();
+        assert_eq!(
+            vec!["(check (= expr1 (Num 1)))", "(print-size Num)"],
+            command_strs[command_strs.len() - 2..],
+            "Queries should be emitted after the schedules, in call order."
+        );
+        assert!(command_strs[command_strs.len() - 3].starts_with("(run-schedule"));
+    }
+
     #[test]
     fn egglog_program_method_order_sorts_only() {
         let sorts_data = EgglogSorts::default();
@@ -621,6 +1116,170 @@ mod tests {
             .schedules(schedule1)
             .bindings(symbols1)
             .program();
-        let _egglog_program_str = egglog_program.to_string();
+        let egglog_program_str = egglog_program.to_string();
+        let (reparsed_egglog_program, unrouted_cmds) =
+            EgglogProgram::from_egglog_str(&egglog_program_str).unwrap();
+        assert!(unrouted_cmds.is_empty());
+        let egglog_program_cmds: EgglogCommandList = egglog_program.into();
+        let reparsed_egglog_program_cmds: EgglogCommandList = reparsed_egglog_program.into();
+        assert_eq!(
+            egglog_program_cmds
+                .iter()
+                .map(Command::to_string)
+                .collect_vec(),
+            reparsed_egglog_program_cmds
+                .iter()
+                .map(Command::to_string)
+                .collect_vec(),
+            "Printed program should parse back into the same commands."
+        );
+    }
+
+    #[test]
+    fn extract_bound_symbols() {
+        let sorts =
+            EgglogSorts::default().add_sort_str("(datatype Math (Num i64) (Add Math Math))");
+        let facts = EgglogFacts::default().add_facts_str(indoc! {"
+            (let expr1 (Add (Num 1) (Num 2)))
+            (let expr2 (Num 3))
+        "});
+        let egglog_program = EgglogProgramBuilder::initialize()
+            .sorts(sorts.clone())
+            .facts(facts.clone())
+            .bindings([Symbol::new("expr2")].into())
+            .extract_bindings(2)
+            .try_variables()
+            .unwrap();
+        let egglog_program_cmds: EgglogCommandList = egglog_program.into();
+        assert_eq!(4, egglog_program_cmds.len());
+        assert!(matches!(
+            egglog_program_cmds[3],
+            Command::QueryExtract { variants: 2, .. }
+        ));
+        if let Err(err_msg) = EGraph::default().run_program(egglog_program_cmds) {
+            panic!("Failure to run program: {:?}", err_msg);
+        }
+
+        let unbound_program = EgglogProgramBuilder::initialize()
+            .sorts(sorts)
+            .facts(facts)
+            .bindings([Symbol::new("expr3")].into())
+            .try_variables();
+        assert!(matches!(
+            unbound_program,
+            Err(EgglogProgramError::UnboundBindings { .. })
+        ));
+    }
+
+    #[test]
+    fn accumulate_builder_sections() {
+        let sorts =
+            EgglogSorts::default().add_sort_str("(datatype Math (Num i64) (Add Math Math))");
+        let egglog_program = EgglogProgramBuilder::initialize()
+            .sorts(sorts)
+            .facts(EgglogFacts::default().add_facts_str("(let expr1 (Num 1))"))
+            .rules(EgglogRules::default().add_rule_str(indoc! {"
+                (ruleset comm-rules)
+                (rewrite (Add a b) (Add b a) :ruleset comm-rules)
+            "}))
+            .schedules(
+                EgglogSchedules::default().add_schedule_str("(run-schedule (run comm-rules))"),
+            )
+            .bindings([Symbol::new("expr1")].into())
+            .facts(EgglogFacts::default().add_facts_str("(let expr2 (Add expr1 (Num 2)))"))
+            .rules(EgglogRules::default().add_rule_str(indoc! {"
+                (ruleset zero-rules)
+                (rewrite (Add a (Num 0)) a :ruleset zero-rules)
+            "}))
+            .schedules(
+                EgglogSchedules::default().add_schedule_str("(run-schedule (run zero-rules))"),
+            )
+            .try_program()
+            .unwrap();
+        assert_eq!(
+            2,
+            egglog_program.facts.1.len(),
+            "Both facts sections should be kept."
+        );
+        assert_eq!(2, egglog_program.facts.0.len());
+        assert_eq!(2, egglog_program.rules.len());
+        assert_eq!(2, egglog_program.schedules.len());
+        assert!(egglog_program.bindings.contains(&Symbol::new("expr1")));
+    }
+
+    #[test]
+    fn reject_rebound_facts() {
+        let sorts = EgglogSorts::default().add_sort_str("(datatype Math (Num i64))");
+        let egglog_program = EgglogProgramBuilder::initialize()
+            .sorts(sorts)
+            .facts(EgglogFacts::default().add_facts_str("(let expr1 (Num 1))"))
+            .facts(EgglogFacts::default().add_facts_str("(let expr1 (Num 3))"))
+            .try_definitions();
+        assert!(
+            matches!(
+                egglog_program,
+                Err(EgglogProgramError::Build(BuildError::DuplicateFact { symbol }))
+                    if symbol == Symbol::new("expr1")
+            ),
+            "Re-binding a fact should be returned from the try terminals."
+        );
+    }
+
+    #[test]
+    fn partition_egglog_program_from_str() {
+        let program_str = indoc! {"
+            (datatype Math (Num i64) (Add Math Math))
+            (let expr1 (Add (Num 1) (Num 2)))
+            (ruleset math-rules)
+            (rewrite (Add a b) (Add b a) :ruleset math-rules)
+            (run-schedule (saturate (run math-rules)))
+            (check (= expr1 (Add (Num 2) (Num 1))))
+        "};
+        let (egglog_program, unrouted_cmds) = EgglogProgram::from_egglog_str(program_str).unwrap();
+        assert_eq!(1, egglog_program.sorts.1.len());
+        assert_eq!(3, egglog_program.sorts.0.len());
+        assert_eq!(1, egglog_program.facts.1.len());
+        assert!(egglog_program.facts.0.contains(&Symbol::new("expr1")));
+        assert_eq!(1, egglog_program.rules.len());
+        assert_eq!(2, egglog_program.rules[0].len());
+        assert_eq!(1, egglog_program.schedules.len());
+        assert_eq!(1, egglog_program.queries.len());
+        assert!(
+            unrouted_cmds.is_empty(),
+            "Every command should be routed into a program section."
+        );
+    }
+
+    #[test]
+    fn partition_keeps_order_sensitive_commands_unrouted() {
+        let program_str = indoc! {"
+            (datatype Math (Num i64) (Add Math Math))
+            (let expr1 (Add (Num 1) (Num 2)))
+            (ruleset math-rules)
+            (rewrite (Add a b) (Add b a) :ruleset math-rules)
+            (run-schedule (run math-rules))
+            (let expr2 (Add (Num 2) (Num 1)))
+            (check (= expr1 expr2))
+        "};
+        let (egglog_program, unrouted_cmds) = EgglogProgram::from_egglog_str(program_str).unwrap();
+        assert_eq!(1, egglog_program.facts.1.len());
+        assert_eq!(1, egglog_program.schedules.len());
+        assert!(egglog_program.queries.is_empty());
+        let order_sensitive_cmds = parse_egglog_str(
+            None,
+            "(let expr2 (Add (Num 2) (Num 1))) (check (= expr1 expr2))",
+        )
+        .unwrap();
+        assert_eq!(
+            order_sensitive_cmds
+                .iter()
+                .map(|command| command.to_string())
+                .collect_vec(),
+            unrouted_cmds
+                .iter()
+                .map(|command| command.to_string())
+                .collect_vec(),
+            "A let after a schedule should not be moved in front of it."
+        );
     }
 }
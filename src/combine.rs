@@ -0,0 +1,788 @@
+use std::fmt::Display;
+use std::ops::Deref;
+
+use egglog::ast::{Command, Symbol};
+use frunk::semigroup::Semigroup;
+use indexmap::IndexMap;
+use itertools::{Either, Itertools};
+
+use crate::builder::{fact_symbols, sort_symbols};
+use crate::egraph::egglog_names::EGGLOG_DEFAULT_RULESET;
+use crate::facts::get_fact_symbol;
+use crate::rename::EgglogRenames;
+use crate::rules::get_rule_ruleset;
+use crate::sorts::get_sort_symbol;
+use crate::{
+    EgglogCommandList, EgglogFacts, EgglogProgram, EgglogProgramError, EgglogQueries, EgglogRules,
+    EgglogSchedules, EgglogSection, EgglogSorts, EgglogSymbols,
+};
+
+pub(crate) fn same_definition(existing: &Command, incoming: &Command) -> bool {
+    existing.to_string() == incoming.to_string()
+}
+
+pub(crate) fn extend_datatype(existing: &Command, incoming: &Command) -> Option<Command> {
+    match (existing, incoming) {
+        (
+            Command::Datatype { name, variants, .. },
+            Command::Datatype {
+                name: incoming_name,
+                variants: incoming_variants,
+                ..
+            },
+        ) if name == incoming_name => {
+            let conflicting_variant = incoming_variants.iter().any(|incoming_variant| {
+                variants.iter().any(|variant| {
+                    variant.name == incoming_variant.name && variant.types != incoming_variant.types
+                })
+            });
+            if conflicting_variant {
+                return None;
+            }
+            let new_variants = incoming_variants
+                .iter()
+                .filter(|incoming_variant| {
+                    !variants
+                        .iter()
+                        .any(|variant| variant.name == incoming_variant.name)
+                })
+                .cloned()
+                .collect_vec();
+            let mut extended_datatype = existing.clone();
+            if let Command::Datatype { variants, .. } = &mut extended_datatype {
+                variants.extend(new_variants);
+            }
+            Some(extended_datatype)
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn extend_datatypes(
+    sorts: &EgglogSorts,
+    sorts_update: &EgglogSorts,
+) -> (EgglogSorts, EgglogCommandList) {
+    let mut extended_sorts = sorts.iter().cloned().collect_vec();
+    let mut remaining_sorts = vec![];
+    for incoming in sorts_update.iter() {
+        let extension = extended_sorts
+            .iter()
+            .enumerate()
+            .find_map(|(index, existing)| {
+                extend_datatype(existing, incoming).map(|extended| (index, extended))
+            });
+        match extension {
+            Some((index, extended_datatype)) => extended_sorts[index] = extended_datatype,
+            None => remaining_sorts.push(incoming.clone()),
+        }
+    }
+    (
+        EgglogSorts::default().add_sorts(extended_sorts),
+        remaining_sorts,
+    )
+}
+
+fn compatible_sorts(existing: &Command, incoming: &Command) -> bool {
+    same_definition(existing, incoming) || extend_datatype(existing, incoming).is_some()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    #[default]
+    KeepLeft,
+    KeepRight,
+    ErrorOnConflict,
+    RenameIncoming,
+    Append,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergePolicy {
+    pub sorts: MergeStrategy,
+    pub facts: MergeStrategy,
+    pub rules: MergeStrategy,
+    pub bindings: MergeStrategy,
+    pub schedules: MergeStrategy,
+    pub queries: MergeStrategy,
+}
+
+impl MergePolicy {
+    pub fn uniform(strategy: MergeStrategy) -> Self {
+        Self {
+            sorts: strategy,
+            facts: strategy,
+            rules: strategy,
+            bindings: strategy,
+            schedules: strategy,
+            queries: strategy,
+        }
+    }
+}
+
+impl Default for MergePolicy {
+    fn default() -> Self {
+        Self {
+            sorts: MergeStrategy::KeepLeft,
+            facts: MergeStrategy::KeepLeft,
+            rules: MergeStrategy::Append,
+            bindings: MergeStrategy::Append,
+            schedules: MergeStrategy::Append,
+            queries: MergeStrategy::Append,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CombineConflict {
+    pub section: EgglogSection,
+    pub symbol: Symbol,
+    pub existing: Command,
+    pub incoming: Command,
+}
+
+impl Display for CombineConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Conflicting definitions of {} in {}:\n  existing: {}\n  incoming: {}",
+            self.symbol, self.section, self.existing, self.incoming
+        )
+    }
+}
+
+fn section_conflicts<'a, SymbolsOf, Compatible>(
+    section: EgglogSection,
+    existing: impl Iterator<Item = &'a Command> + Clone,
+    incoming: impl Iterator<Item = &'a Command>,
+    symbols_of: SymbolsOf,
+    compatible: Compatible,
+) -> Vec<CombineConflict>
+where
+    SymbolsOf: Fn(&Command) -> Vec<Symbol>,
+    Compatible: Fn(&Command, &Command) -> bool,
+{
+    let mut conflicts = vec![];
+    for incoming_command in incoming {
+        let incoming_symbols = symbols_of(incoming_command);
+        for existing_command in existing.clone() {
+            let shared_symbol = symbols_of(existing_command)
+                .into_iter()
+                .find(|symbol| incoming_symbols.contains(symbol));
+            if let Some(symbol) = shared_symbol {
+                if !compatible(existing_command, incoming_command) {
+                    conflicts.push(CombineConflict {
+                        section,
+                        symbol,
+                        existing: existing_command.clone(),
+                        incoming: incoming_command.clone(),
+                    });
+                }
+            }
+        }
+    }
+    conflicts
+}
+
+// An incoming definition may replace several existing ones; it takes the place
+// of the first and the others are dropped.
+fn keep_incoming_definitions<'a>(
+    existing: impl Iterator<Item = &'a Command>,
+    conflicts: &[CombineConflict],
+) -> EgglogCommandList {
+    let mut kept_definitions = EgglogCommandList::default();
+    let mut placed_definitions: Vec<&Command> = vec![];
+    for command in existing {
+        let replacements = conflicts
+            .iter()
+            .filter(|conflict| same_definition(&conflict.existing, command))
+            .map(|conflict| &conflict.incoming)
+            .collect_vec();
+        if replacements.is_empty() {
+            kept_definitions.push(command.clone());
+        }
+        for replacement in replacements {
+            if !placed_definitions
+                .iter()
+                .any(|placed| same_definition(placed, replacement))
+            {
+                placed_definitions.push(replacement);
+                kept_definitions.push(replacement.clone());
+            }
+        }
+    }
+    kept_definitions
+}
+
+fn without_incoming_definitions<'a>(
+    incoming: impl Iterator<Item = &'a Command>,
+    conflicts: &[CombineConflict],
+) -> EgglogCommandList {
+    incoming
+        .filter(|command| {
+            !conflicts
+                .iter()
+                .any(|conflict| same_definition(&conflict.incoming, command))
+        })
+        .cloned()
+        .collect()
+}
+
+// Schedules and queries have no names, so they are matched by position.
+fn merge_positional_sections<Section, FromCommands>(
+    section: EgglogSection,
+    strategy: MergeStrategy,
+    base_sections: &mut Vec<Section>,
+    incoming_sections: &mut Vec<Section>,
+    conflicts: &mut Vec<CombineConflict>,
+    from_commands: FromCommands,
+) where
+    Section: Deref<Target = EgglogCommandList>,
+    FromCommands: Fn(EgglogCommandList) -> Section,
+{
+    let base_commands = base_sections
+        .iter()
+        .flat_map(|commands| commands.iter())
+        .cloned()
+        .collect_vec();
+    let incoming_commands = incoming_sections
+        .iter()
+        .flat_map(|commands| commands.iter())
+        .cloned()
+        .collect_vec();
+    match strategy {
+        MergeStrategy::Append | MergeStrategy::RenameIncoming => {}
+        MergeStrategy::KeepLeft if !base_commands.is_empty() => incoming_sections.clear(),
+        MergeStrategy::KeepRight if !incoming_commands.is_empty() => base_sections.clear(),
+        MergeStrategy::KeepLeft | MergeStrategy::KeepRight => {}
+        MergeStrategy::ErrorOnConflict if !base_commands.is_empty() => {
+            for (existing, incoming) in base_commands.iter().zip(incoming_commands.iter()) {
+                if !same_definition(existing, incoming) {
+                    conflicts.push(CombineConflict {
+                        section,
+                        symbol: Symbol::from(command_keyword(existing)),
+                        existing: existing.clone(),
+                        incoming: incoming.clone(),
+                    });
+                }
+            }
+            let unmatched_commands = incoming_commands
+                .into_iter()
+                .skip(base_commands.len())
+                .collect_vec();
+            incoming_sections.clear();
+            if !unmatched_commands.is_empty() {
+                incoming_sections.push(from_commands(unmatched_commands));
+            }
+        }
+        MergeStrategy::ErrorOnConflict => {}
+    }
+}
+
+fn command_keyword(command: &Command) -> String {
+    let command_str = command.to_string();
+    command_str
+        .trim_start_matches('(')
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+fn fresh_symbol(symbol: Symbol, taken: &mut EgglogSymbols) -> Symbol {
+    let fresh = (1..)
+        .map(|suffix| Symbol::from(format!("{}_{}", symbol, suffix)))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("Unbounded suffixes always yield a fresh symbol.");
+    taken.insert(fresh);
+    fresh
+}
+
+fn ruleset_commands(program: &EgglogProgram) -> IndexMap<Symbol, Vec<String>> {
+    let mut rulesets: IndexMap<Symbol, Vec<String>> = IndexMap::new();
+    for command in program.rules.iter().flat_map(|rules| rules.iter()) {
+        if let Some(ruleset) = get_rule_ruleset(command) {
+            rulesets
+                .entry(ruleset)
+                .or_default()
+                .push(command.to_string());
+        }
+    }
+    rulesets
+}
+
+fn retain_rules<Keep>(program: &mut EgglogProgram, keep: Keep)
+where
+    Keep: Fn(&Command) -> bool,
+{
+    program.rules = program
+        .rules
+        .iter()
+        .map(|rules| {
+            EgglogRules::default().add_rules(rules.iter().filter(|command| keep(command)).cloned())
+        })
+        .collect();
+}
+
+fn differing_rules(
+    base: &EgglogProgram,
+    incoming: &EgglogProgram,
+    ruleset: Symbol,
+) -> Option<(Command, Command)> {
+    let ruleset_rules = |program: &EgglogProgram| {
+        program
+            .rules
+            .iter()
+            .flat_map(|rules| rules.iter())
+            .filter(|command| get_rule_ruleset(command) == Some(ruleset))
+            .cloned()
+            .collect_vec()
+    };
+    let (base_rules, incoming_rules) = (ruleset_rules(base), ruleset_rules(incoming));
+    base_rules
+        .iter()
+        .zip(incoming_rules.iter())
+        .find(|(existing, incoming)| !same_definition(existing, incoming))
+        .or(base_rules.first().zip(incoming_rules.first()))
+        .map(|(existing, incoming)| (existing.clone(), incoming.clone()))
+}
+
+impl EgglogProgram {
+    pub fn combine_conflicts(&self, program_update: &Self) -> Vec<CombineConflict> {
+        let mut conflicts = section_conflicts(
+            EgglogSection::Sorts,
+            self.sorts().1.iter(),
+            program_update.sorts().1.iter(),
+            get_sort_symbol,
+            compatible_sorts,
+        );
+        conflicts.append(&mut section_conflicts(
+            EgglogSection::Facts,
+            self.facts().1.iter(),
+            program_update.facts().1.iter(),
+            |fact| vec![get_fact_symbol(fact)],
+            same_definition,
+        ));
+        conflicts
+    }
+
+    pub fn combine_with(
+        &self,
+        program_update: &Self,
+        policy: MergePolicy,
+    ) -> Result<Self, EgglogProgramError> {
+        // Appending same-named declarations or let-bindings would redefine them.
+        for (section, strategy) in [
+            (EgglogSection::Sorts, policy.sorts),
+            (EgglogSection::Facts, policy.facts),
+        ] {
+            if strategy == MergeStrategy::Append {
+                return Err(EgglogProgramError::UnsupportedMergeStrategy { section, strategy });
+            }
+        }
+        let mut base = self.clone();
+        let mut incoming = program_update.clone();
+        let mut conflicts = vec![];
+        let mut renames = EgglogRenames::default();
+        let mut taken: EgglogSymbols = [
+            &self.sorts.0,
+            &self.facts.0,
+            &program_update.sorts.0,
+            &program_update.facts.0,
+        ]
+        .into_iter()
+        .flatten()
+        .copied()
+        .collect();
+
+        let sort_conflicts = section_conflicts(
+            EgglogSection::Sorts,
+            self.sorts.1.iter(),
+            program_update.sorts.1.iter(),
+            get_sort_symbol,
+            compatible_sorts,
+        );
+        match policy.sorts {
+            MergeStrategy::KeepLeft | MergeStrategy::Append => {}
+            MergeStrategy::KeepRight => {
+                let sorts = EgglogSorts::default().add_sorts(keep_incoming_definitions(
+                    self.sorts.1.iter(),
+                    &sort_conflicts,
+                ));
+                base.sorts = (sort_symbols(&sorts), sorts);
+                let sorts = EgglogSorts::default().add_sorts(without_incoming_definitions(
+                    program_update.sorts.1.iter(),
+                    &sort_conflicts,
+                ));
+                incoming.sorts = (sort_symbols(&sorts), sorts);
+            }
+            MergeStrategy::ErrorOnConflict => conflicts.extend(sort_conflicts),
+            MergeStrategy::RenameIncoming => {
+                for conflict in sort_conflicts {
+                    for symbol in get_sort_symbol(&conflict.incoming) {
+                        if self.sorts.0.contains(&symbol) && !renames.contains_key(&symbol) {
+                            renames.insert(symbol, fresh_symbol(symbol, &mut taken));
+                        }
+                    }
+                }
+            }
+        }
+
+        let fact_conflicts = section_conflicts(
+            EgglogSection::Facts,
+            self.facts.1.iter(),
+            program_update.facts.1.iter(),
+            |fact| vec![get_fact_symbol(fact)],
+            same_definition,
+        );
+        match policy.facts {
+            MergeStrategy::KeepLeft | MergeStrategy::Append => {}
+            MergeStrategy::KeepRight => {
+                let facts = EgglogFacts::default().add_facts(keep_incoming_definitions(
+                    self.facts.1.iter(),
+                    &fact_conflicts,
+                ));
+                base.facts = (fact_symbols(&facts), facts);
+                let facts = EgglogFacts::default().add_facts(without_incoming_definitions(
+                    program_update.facts.1.iter(),
+                    &fact_conflicts,
+                ));
+                incoming.facts = (fact_symbols(&facts), facts);
+            }
+            MergeStrategy::ErrorOnConflict => conflicts.extend(fact_conflicts),
+            MergeStrategy::RenameIncoming => {
+                for conflict in fact_conflicts {
+                    let symbol = get_fact_symbol(&conflict.incoming);
+                    renames.insert(symbol, fresh_symbol(symbol, &mut taken));
+                }
+            }
+        }
+
+        let base_rulesets = ruleset_commands(self);
+        let incoming_rulesets = ruleset_commands(program_update);
+        taken.extend(base_rulesets.keys().chain(incoming_rulesets.keys()));
+        let (duplicate_rulesets, conflicting_rulesets): (Vec<Symbol>, Vec<Symbol>) =
+            incoming_rulesets
+                .iter()
+                .filter(|(ruleset, _commands)| base_rulesets.contains_key(*ruleset))
+                .partition_map(|(ruleset, commands)| {
+                    if base_rulesets[ruleset] == *commands {
+                        Either::Left(*ruleset)
+                    } else {
+                        Either::Right(*ruleset)
+                    }
+                });
+        let in_rulesets = |rulesets: &[Symbol], command: &Command| {
+            get_rule_ruleset(command).is_some_and(|ruleset| rulesets.contains(&ruleset))
+        };
+        match policy.rules {
+            MergeStrategy::Append => {}
+            MergeStrategy::KeepLeft => retain_rules(&mut incoming, |command| {
+                !in_rulesets(&duplicate_rulesets, command)
+                    && !in_rulesets(&conflicting_rulesets, command)
+            }),
+            MergeStrategy::KeepRight => retain_rules(&mut base, |command| {
+                !in_rulesets(&duplicate_rulesets, command)
+                    && !in_rulesets(&conflicting_rulesets, command)
+            }),
+            MergeStrategy::ErrorOnConflict => {
+                for ruleset in conflicting_rulesets.iter().copied() {
+                    if let Some((existing, incoming)) =
+                        differing_rules(self, program_update, ruleset)
+                    {
+                        conflicts.push(CombineConflict {
+                            section: EgglogSection::Rules,
+                            symbol: ruleset,
+                            existing,
+                            incoming,
+                        });
+                    }
+                }
+                retain_rules(&mut incoming, |command| {
+                    !in_rulesets(&duplicate_rulesets, command)
+                });
+            }
+            MergeStrategy::RenameIncoming => {
+                for ruleset in conflicting_rulesets.iter().copied() {
+                    if ruleset.as_str() != EGGLOG_DEFAULT_RULESET {
+                        renames.insert(ruleset, fresh_symbol(ruleset, &mut taken));
+                    }
+                }
+                retain_rules(&mut incoming, |command| {
+                    !in_rulesets(&duplicate_rulesets, command)
+                });
+            }
+        }
+
+        // Bindings only name let-bound facts, so every other strategy takes their
+        // union.
+        match policy.bindings {
+            MergeStrategy::KeepLeft if !self.bindings.is_empty() => incoming.bindings.clear(),
+            MergeStrategy::KeepRight if !program_update.bindings.is_empty() => {
+                base.bindings.clear()
+            }
+            _ => {}
+        }
+
+        merge_positional_sections(
+            EgglogSection::Schedules,
+            policy.schedules,
+            &mut base.schedules,
+            &mut incoming.schedules,
+            &mut conflicts,
+            |schedules| EgglogSchedules::default().add_schedule(schedules),
+        );
+        merge_positional_sections(
+            EgglogSection::Queries,
+            policy.queries,
+            &mut base.queries,
+            &mut incoming.queries,
+            &mut conflicts,
+            |queries| EgglogQueries::default().add_queries(queries),
+        );
+
+        if !conflicts.is_empty() {
+            return Err(EgglogProgramError::CombineConflict(conflicts));
+        }
+        if !renames.is_empty() {
+            incoming.apply_renames(&renames, false);
+        }
+        Ok(base.combine(&incoming))
+    }
+
+    pub fn combine_checked(&self, program_update: &Self) -> (Self, Vec<CombineConflict>) {
+        (
+            self.combine(program_update),
+            self.combine_conflicts(program_update),
+        )
+    }
+
+    pub fn try_combine(&self, program_update: &Self) -> Result<Self, EgglogProgramError> {
+        let conflicts = self.combine_conflicts(program_update);
+        if conflicts.is_empty() {
+            Ok(self.combine(program_update))
+        } else {
+            Err(EgglogProgramError::CombineConflict(conflicts))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn combine_detects_conflicting_definitions() {
+        let (egglog_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(indoc! {"
+            (datatype LLHDTy (IntTy i64) (VoidTy))
+            (datatype Math (Num i64))
+            (let expr1 (Num 1))
+        "})
+        .unwrap();
+        let (duplicate_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(indoc! {"
+            (datatype Math (Num i64))
+            (let expr1 (Num 1))
+        "})
+        .unwrap();
+        let deduplicated_program = egglog_program.try_combine(&duplicate_program).unwrap();
+        assert_eq!(2, deduplicated_program.sorts().1.len());
+        assert_eq!(1, deduplicated_program.facts().1.len());
+
+        let (conflicting_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(indoc! {"
+            (datatype LLHDTy (IntTy String) (PointerTy LLHDTy))
+            (let expr1 (Num 2))
+        "})
+        .unwrap();
+        let conflicts = egglog_program.combine_conflicts(&conflicting_program);
+        assert_eq!(2, conflicts.len(), "Both redefinitions should conflict.");
+        assert_eq!(Symbol::new("LLHDTy"), conflicts[0].symbol);
+        assert_eq!(EgglogSection::Facts, conflicts[1].section);
+        assert!(matches!(
+            egglog_program.try_combine(&conflicting_program),
+            Err(EgglogProgramError::CombineConflict(..))
+        ));
+    }
+
+    #[test]
+    fn combine_with_merge_policies() {
+        let (base_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(indoc! {"
+            (datatype Math (Num i64) (Add Math Math))
+            (let expr1 (Add (Num 1) (Num 2)))
+            (ruleset math-rules)
+            (rewrite (Add a b) (Add b a) :ruleset math-rules)
+            (run-schedule (run math-rules))
+        "})
+        .unwrap();
+        let (vendor_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(indoc! {"
+            (datatype Math (Num i64 i64) (Mul Math Math))
+            (let expr1 (Mul (Num 1 1) (Num 2 2)))
+            (ruleset math-rules)
+            (rewrite (Mul a b) (Mul b a) :ruleset math-rules)
+            (run-schedule (saturate (run math-rules)))
+        "})
+        .unwrap();
+
+        let overridden_program = base_program
+            .combine_with(
+                &vendor_program,
+                MergePolicy::uniform(MergeStrategy::KeepRight),
+            )
+            .unwrap();
+        assert_eq!(1, overridden_program.sorts().1.len());
+        assert!(overridden_program.sorts().1[0].to_string().contains("Mul"));
+        assert!(overridden_program.facts().1[0].to_string().contains("Mul"));
+        assert!(overridden_program.to_string().contains("saturate"));
+
+        let renamed_program = base_program
+            .combine_with(
+                &vendor_program,
+                MergePolicy::uniform(MergeStrategy::RenameIncoming),
+            )
+            .unwrap();
+        assert_eq!(2, renamed_program.sorts().1.len());
+        assert!(renamed_program.sorts().0.contains(&Symbol::new("Math_1")));
+        assert!(renamed_program.facts().0.contains(&Symbol::new("expr1_1")));
+        assert!(renamed_program
+            .to_string()
+            .contains("(run-schedule (saturate (run math-rules_1)))"));
+
+        assert!(matches!(
+            base_program.combine_with(
+                &vendor_program,
+                MergePolicy::uniform(MergeStrategy::ErrorOnConflict)
+            ),
+            Err(EgglogProgramError::CombineConflict(conflicts)) if conflicts.len() == 4
+        ));
+    }
+
+    #[test]
+    fn combine_keeps_unmatched_incoming_schedules() {
+        let (base_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(indoc! {"
+            (ruleset math-rules)
+            (run-schedule (run math-rules))
+        "})
+        .unwrap();
+        let (vendor_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(indoc! {"
+            (ruleset math-rules)
+            (run-schedule (run math-rules))
+            (run-schedule (saturate (run math-rules)))
+        "})
+        .unwrap();
+        let schedule_policy = MergePolicy {
+            schedules: MergeStrategy::ErrorOnConflict,
+            ..MergePolicy::default()
+        };
+        let combined_program = base_program
+            .combine_with(&vendor_program, schedule_policy)
+            .unwrap();
+        let schedule_strs = combined_program
+            .schedules()
+            .iter()
+            .flat_map(|schedules| schedules.iter())
+            .map(|schedule| schedule.to_string())
+            .collect_vec();
+        assert_eq!(
+            2,
+            schedule_strs.len(),
+            "Matching schedules should be merged and the extra schedule appended."
+        );
+        assert!(schedule_strs[1].contains("saturate"));
+    }
+
+    #[test]
+    fn combine_with_every_conflict_and_section() {
+        let (base_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(indoc! {"
+            (datatype Lhs (X i64))
+            (datatype Rhs (Y i64))
+            (check (= (X 1) (X 1)))
+        "})
+        .unwrap();
+        let (vendor_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(indoc! {"
+            (datatype Pair (X String) (Y String))
+            (check (= (X \"a\") (X \"a\")))
+        "})
+        .unwrap();
+        assert_eq!(
+            2,
+            base_program.combine_conflicts(&vendor_program).len(),
+            "Every existing datatype the incoming one collides with should be reported."
+        );
+
+        let overridden_program = base_program
+            .combine_with(
+                &vendor_program,
+                MergePolicy::uniform(MergeStrategy::KeepRight),
+            )
+            .unwrap();
+        assert_eq!(
+            vec!["(datatype Pair (X String) (Y String))".to_owned()],
+            overridden_program
+                .sorts()
+                .1
+                .iter()
+                .map(|sort| sort.to_string())
+                .collect_vec(),
+            "Both overridden datatypes should be replaced by the incoming one."
+        );
+        let query_strs = |program: &EgglogProgram| {
+            program
+                .queries()
+                .iter()
+                .flat_map(|queries| queries.iter())
+                .map(|query| query.to_string())
+                .collect_vec()
+        };
+        assert_eq!(
+            query_strs(&vendor_program),
+            query_strs(&overridden_program),
+            "Queries should follow the merge policy."
+        );
+        let kept_program = base_program
+            .combine_with(
+                &vendor_program,
+                MergePolicy {
+                    sorts: MergeStrategy::RenameIncoming,
+                    ..MergePolicy::uniform(MergeStrategy::KeepLeft)
+                },
+            )
+            .unwrap();
+        assert_eq!(query_strs(&base_program), query_strs(&kept_program));
+
+        assert!(matches!(
+            base_program.combine_with(&vendor_program, MergePolicy::uniform(MergeStrategy::Append)),
+            Err(EgglogProgramError::UnsupportedMergeStrategy {
+                section: EgglogSection::Sorts,
+                strategy: MergeStrategy::Append,
+            })
+        ));
+    }
+
+    #[test]
+    fn combine_extends_datatype_variants() {
+        let (base_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(indoc! {"
+            (datatype LLHDInst (Add i64 i64) (Sub i64 i64))
+            (let inst1 (Add 1 2))
+        "})
+        .unwrap();
+        let (extension_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(indoc! {"
+            (datatype LLHDInst (Add i64 i64) (Shl i64 i64))
+            (let inst2 (Shl 1 2))
+        "})
+        .unwrap();
+        assert!(base_program
+            .combine_conflicts(&extension_program)
+            .is_empty());
+        let extended_program = base_program.combine(&extension_program);
+        assert_eq!(1, extended_program.sorts().1.len());
+        assert_eq!(
+            "(datatype LLHDInst (Add i64 i64) (Sub i64 i64) (Shl i64 i64))",
+            extended_program.sorts().1[0].to_string()
+        );
+        assert!(extended_program.sorts().0.contains(&Symbol::new("Shl")));
+        assert_eq!(
+            extended_program.to_string(),
+            (base_program.clone() + extension_program).to_string()
+        );
+    }
+}
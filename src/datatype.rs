@@ -0,0 +1,129 @@
+use egglog::ast::{Command, Symbol};
+
+use crate::EgglogSorts;
+
+pub trait EgglogDatatype {
+    fn egglog_sort_name() -> Symbol;
+
+    fn egglog_datatype() -> Command;
+}
+
+impl EgglogSorts {
+    pub fn add_datatype<Datatype: EgglogDatatype>(self) -> Self {
+        self.add_sorts([Datatype::egglog_datatype()])
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use egglog::EGraph;
+    use indoc::indoc;
+
+    use super::*;
+    use crate::{
+        EgglogDatatype, EgglogFacts, EgglogProgramBuilder, EgglogRules, EgglogSchedules,
+        FromEgglogTerm, ToEgglogExpr,
+    };
+
+    #[allow(dead_code)]
+    #[derive(Debug, PartialEq, EgglogDatatype, ToEgglogExpr, FromEgglogTerm)]
+    enum Math {
+        Num(i64),
+        Var(String),
+        #[egglog(cost = 2)]
+        Add(Box<Math>, Box<Math>),
+        #[egglog(name = "Mul", cost = 4)]
+        Times {
+            lhs: Box<Math>,
+            rhs: Box<Math>,
+        },
+    }
+
+    #[derive(Debug, PartialEq, EgglogDatatype, ToEgglogExpr, FromEgglogTerm)]
+    struct Register {
+        index: u8,
+        width: usize,
+    }
+
+    #[test]
+    fn derive_datatype_from_enum() {
+        assert_eq!(
+            "(datatype Math (Num i64) (Var String) (Add Math Math :cost 2) (Mul Math Math :cost 4))",
+            Math::egglog_datatype().to_string(),
+            "Derived datatype should follow the enum variants."
+        );
+        let sorts = EgglogSorts::default().add_datatype::<Math>();
+        let expr1 = Math::Times {
+            lhs: Box::new(Math::Num(1)),
+            rhs: Box::new(Math::Var(String::from("x"))),
+        };
+        assert_eq!(
+            "(Mul (Num 1) (Var \"x\"))",
+            expr1.to_egglog_expr().to_string(),
+            "Derived expressions should use the egglog variant names."
+        );
+        let facts = EgglogFacts::default().add_let("expr1", &expr1);
+        let egglog_program = EgglogProgramBuilder::initialize()
+            .sorts(sorts)
+            .facts(facts)
+            .bindings([Symbol::new("expr1")].into())
+            .try_variables();
+        assert!(egglog_program.is_ok());
+    }
+
+    #[test]
+    fn round_trip_rust_values_through_egraph() {
+        let expr1 = Math::Add(
+            Box::new(Math::Var(String::from("x"))),
+            Box::new(Math::Num(0)),
+        );
+        let egglog_program = EgglogProgramBuilder::initialize()
+            .sorts(EgglogSorts::default().add_datatype::<Math>())
+            .facts(EgglogFacts::default().add_let("expr1", &expr1))
+            .rules(EgglogRules::default().add_rule_str(indoc! {"
+                (ruleset zero-rules)
+                (rewrite (Add a (Num 0)) a :ruleset zero-rules)
+            "}))
+            .schedules(
+                EgglogSchedules::default().add_schedule_str("(run-schedule (run zero-rules))"),
+            )
+            .bindings([Symbol::new("expr1")].into())
+            .try_program()
+            .unwrap();
+        let mut egraph = EGraph::default();
+        egglog_program.run(&mut egraph).unwrap();
+        let extractions = egglog_program.extract_bound_terms(&mut egraph, 0).unwrap();
+        assert_eq!(
+            Ok(Math::Var(String::from("x"))),
+            extractions[&Symbol::new("expr1")].decode::<Math>(),
+            "The optimized term should decode back into the Rust value."
+        );
+    }
+
+    #[test]
+    fn round_trip_unsigned_fields_through_egraph() {
+        assert_eq!(
+            "(datatype Register (Register i64 i64))",
+            Register::egglog_datatype().to_string(),
+            "Unsigned fields should use the i64 sort of egglog integer literals."
+        );
+        let register = Register {
+            index: 3,
+            width: 64,
+        };
+        let egglog_program = EgglogProgramBuilder::initialize()
+            .sorts(EgglogSorts::default().add_datatype::<Register>())
+            .facts(EgglogFacts::default().add_let("register", &register))
+            .bindings([Symbol::new("register")].into())
+            .try_variables()
+            .unwrap();
+        let mut egraph = EGraph::default();
+        egglog_program.run(&mut egraph).unwrap();
+        let extractions = egglog_program.extract_bound_terms(&mut egraph, 0).unwrap();
+        assert_eq!(
+            Ok(register),
+            extractions[&Symbol::new("register")].decode::<Register>(),
+            "Unsigned fields should survive a run through the e-graph."
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use std::fmt::Display;
+use std::ops::Deref;
+
+use egglog::ast::{Command, GenericAction, GenericSchedule, Span};
+use itertools::Itertools;
+
+use crate::{EgglogCommandList, EgglogProgramError, EgglogSection};
+
+pub(crate) fn command_span(command: &Command) -> Option<Span> {
+    match command {
+        Command::Sort(span, ..)
+        | Command::Datatype { span, .. }
+        | Command::Datatypes { span, .. }
+        | Command::Relation { span, .. }
+        | Command::Function { span, .. }
+        | Command::Constructor { span, .. }
+        | Command::QueryExtract { span, .. }
+        | Command::Check(span, ..)
+        | Command::PrintFunction(span, ..)
+        | Command::PrintSize(span, ..)
+        | Command::Output { span, .. }
+        | Command::Input { span, .. }
+        | Command::Pop(span, ..)
+        | Command::Fail(span, ..)
+        | Command::Include(span, ..) => Some(span.clone()),
+        Command::Rule { rule, .. } => Some(rule.span.clone()),
+        Command::Rewrite(_ruleset, rewrite, _subsume) => Some(rewrite.span.clone()),
+        Command::BiRewrite(_ruleset, rewrite) => Some(rewrite.span.clone()),
+        Command::Action(action) => match action {
+            GenericAction::Let(span, ..)
+            | GenericAction::Set(span, ..)
+            | GenericAction::Change(span, ..)
+            | GenericAction::Union(span, ..)
+            | GenericAction::Extract(span, ..)
+            | GenericAction::Panic(span, ..)
+            | GenericAction::Expr(span, ..) => Some(span.clone()),
+        },
+        Command::RunSchedule(schedule) => match schedule {
+            GenericSchedule::Saturate(span, ..)
+            | GenericSchedule::Repeat(span, ..)
+            | GenericSchedule::Run(span, ..)
+            | GenericSchedule::Sequence(span, ..) => Some(span.clone()),
+        },
+        Command::SetOption { value, .. } => Some(value.span()),
+        // AddRuleset, Push, PrintOverallStatistics and the remaining commands
+        // carry no span in the egglog AST.
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RejectedCommand {
+    pub section: EgglogSection,
+    pub span: Option<Span>,
+    pub command: Command,
+}
+
+impl Display for RejectedCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.span {
+            Some(span) => write!(
+                f,
+                "{} rejected from {}: {}",
+                span, self.section, self.command
+            ),
+            None => write!(f, "Rejected from {}: {}", self.section, self.command),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics(Vec<RejectedCommand>);
+
+impl Diagnostics {
+    pub(crate) fn from_rejected(section: EgglogSection, commands: EgglogCommandList) -> Self {
+        Self(
+            commands
+                .into_iter()
+                .map(|command| RejectedCommand {
+                    section,
+                    span: command_span(&command),
+                    command,
+                })
+                .collect_vec(),
+        )
+    }
+
+    pub fn extend(&mut self, diagnostics: Diagnostics) {
+        self.0.extend(diagnostics.0);
+    }
+
+    pub fn strict(self) -> Result<(), EgglogProgramError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(EgglogProgramError::Rejected(self))
+        }
+    }
+}
+
+impl Deref for Diagnostics {
+    type Target = Vec<RejectedCommand>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.iter().join("\n"))
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = RejectedCommand;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::EgglogSorts;
+
+    #[test]
+    fn report_commands_rejected_from_sorts() {
+        let sort_str = indoc! {"
+            (set-option node_limit 1000)
+            (datatype Math (Num i64) (Add Math Math))
+            (let expr1 (Num 1))
+            (check (= expr1 (Num 1)))
+        "};
+        let (sorts, diagnostics) = EgglogSorts::default()
+            .add_sort_str_checked(sort_str)
+            .unwrap();
+        assert_eq!(1, sorts.len(), "Only the datatype belongs in sorts.");
+        assert_eq!(
+            3,
+            diagnostics.len(),
+            "The option, let and check commands should all be reported."
+        );
+        assert!(diagnostics
+            .iter()
+            .all(|rejected| rejected.section == EgglogSection::Sorts));
+        assert!(matches!(diagnostics[2].command, Command::Check(..)));
+        assert!(diagnostics[2].span.is_some());
+        let Command::SetOption { value, .. } = &diagnostics[0].command else {
+            panic!("The set-option command should be rejected first.");
+        };
+        assert_eq!(
+            Some(value.span().to_string()),
+            diagnostics[0].span.as_ref().map(|span| span.to_string()),
+            "A rejected set-option should point at its value."
+        );
+        assert!(matches!(
+            diagnostics.strict(),
+            Err(EgglogProgramError::Rejected(..))
+        ));
+    }
+}
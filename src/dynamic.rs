@@ -0,0 +1,190 @@
+use crate::builder::{append_section, sort_symbols, try_merge_facts};
+use crate::{
+    BuildError, EgglogFacts, EgglogProgram, EgglogProgramFacts, EgglogProgramSorts, EgglogQueries,
+    EgglogQueryList, EgglogRuleList, EgglogRules, EgglogScheduleList, EgglogSchedules, EgglogSorts,
+    EgglogSymbols,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct DynamicProgramBuilder {
+    pub(crate) sorts: Option<EgglogProgramSorts>,
+    pub(crate) facts: Option<EgglogProgramFacts>,
+    pub(crate) rules: Option<EgglogRuleList>,
+    pub(crate) bindings: Option<EgglogSymbols>,
+    pub(crate) schedules: Option<EgglogScheduleList>,
+    pub(crate) queries: Option<EgglogQueryList>,
+    pub(crate) binding_extraction: Option<usize>,
+    pub(crate) error: Option<BuildError>,
+}
+
+impl DynamicProgramBuilder {
+    pub fn initialize() -> Self {
+        Self::default()
+    }
+
+    fn require(&mut self, satisfied: bool, error: BuildError) -> bool {
+        if self.error.is_none() && !satisfied {
+            self.error = Some(error);
+        }
+        self.error.is_none()
+    }
+
+    fn require_sorts(&mut self, operation: &'static str) -> bool {
+        let sorts_defined = self.sorts.is_some();
+        self.require(sorts_defined, BuildError::MissingSorts { operation })
+    }
+
+    pub fn sorts(mut self, sorts: EgglogSorts) -> Self {
+        let sorts_undefined = self.sorts.is_none();
+        if self.require(sorts_undefined, BuildError::DuplicateSorts) {
+            self.sorts = Some((sort_symbols(&sorts), sorts));
+        }
+        self
+    }
+
+    pub fn replace_sorts(mut self, sorts: EgglogSorts) -> Self {
+        self.sorts = Some((sort_symbols(&sorts), sorts));
+        self
+    }
+
+    pub fn facts(mut self, facts: EgglogFacts) -> Self {
+        if self.require_sorts("facts") {
+            match try_merge_facts(self.facts.clone(), facts) {
+                Ok(merged_facts) => self.facts = Some(merged_facts),
+                Err(error) => {
+                    self.require(false, error);
+                }
+            }
+        }
+        self
+    }
+
+    pub fn rules(mut self, rules: EgglogRules) -> Self {
+        if self.require_sorts("rules") {
+            self.rules = append_section(self.rules.take(), rules);
+        }
+        self
+    }
+
+    pub fn bindings(mut self, bindings: EgglogSymbols) -> Self {
+        let facts_present = self.facts.is_some();
+        let bindings_undefined = self.bindings.is_none();
+        if self.require_sorts("bindings")
+            && self.require(facts_present, BuildError::MissingFacts)
+            && self.require(bindings_undefined, BuildError::DuplicateBindings)
+        {
+            self.bindings = Some(bindings);
+        }
+        self
+    }
+
+    pub fn replace_bindings(mut self, bindings: EgglogSymbols) -> Self {
+        let facts_present = self.facts.is_some();
+        if self.require_sorts("bindings") && self.require(facts_present, BuildError::MissingFacts) {
+            self.bindings = Some(bindings);
+        }
+        self
+    }
+
+    pub fn extract_bindings(mut self, variants: usize) -> Self {
+        let bindings_present = self.bindings.is_some();
+        if self.require(bindings_present, BuildError::MissingBindings) {
+            self.binding_extraction = Some(variants);
+        }
+        self
+    }
+
+    pub fn schedules(mut self, schedules: EgglogSchedules) -> Self {
+        let rules_present = self.rules.is_some();
+        if self.require_sorts("schedules") && self.require(rules_present, BuildError::MissingRules)
+        {
+            self.schedules = append_section(self.schedules.take(), schedules);
+        }
+        self
+    }
+
+    pub fn queries(mut self, queries: EgglogQueries) -> Self {
+        if self.require_sorts("queries") {
+            self.queries = append_section(self.queries.take(), queries);
+        }
+        self
+    }
+
+    pub fn finish(mut self) -> Result<EgglogProgram, BuildError> {
+        self.require_sorts("finish");
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        Ok(EgglogProgram {
+            sorts: self.sorts.unwrap_or_default(),
+            facts: self.facts.unwrap_or_default(),
+            rules: self.rules.unwrap_or_default(),
+            bindings: self.bindings.unwrap_or_default(),
+            schedules: self.schedules.unwrap_or_default(),
+            queries: self.queries.unwrap_or_default(),
+            binding_extraction: self.binding_extraction,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egglog::ast::Symbol;
+
+    use super::*;
+    use crate::EgglogProgramBuilder;
+
+    #[test]
+    fn dynamic_builder_matches_typestate_builder() {
+        let sorts =
+            EgglogSorts::default().add_sort_str("(datatype Math (Num i64) (Add Math Math))");
+        let facts = EgglogFacts::default().add_facts_str("(let expr1 (Add (Num 1) (Num 2)))");
+        let rules = EgglogRules::default()
+            .add_rule_str("(ruleset math-rules) (rewrite (Add a b) (Add b a) :ruleset math-rules)");
+        let schedules =
+            EgglogSchedules::default().add_schedule_str("(run-schedule (run math-rules))");
+        let bindings: EgglogSymbols = [Symbol::new("expr1")].into();
+        let include_rules = true;
+
+        let mut dynamic_builder = DynamicProgramBuilder::initialize()
+            .sorts(sorts.clone())
+            .facts(facts.clone())
+            .bindings(bindings.clone());
+        if include_rules {
+            dynamic_builder = dynamic_builder
+                .rules(rules.clone())
+                .schedules(schedules.clone());
+        }
+        let dynamic_program = dynamic_builder.finish().unwrap();
+        let typestate_program = EgglogProgramBuilder::initialize()
+            .sorts(sorts.clone())
+            .facts(facts.clone())
+            .bindings(bindings)
+            .rules(rules)
+            .schedules(schedules.clone())
+            .program();
+        assert_eq!(typestate_program.to_string(), dynamic_program.to_string());
+
+        let rebound_program = DynamicProgramBuilder::initialize()
+            .sorts(sorts.clone())
+            .facts(facts.clone())
+            .facts(facts)
+            .finish();
+        assert!(
+            matches!(
+                rebound_program,
+                Err(BuildError::DuplicateFact { symbol }) if symbol == Symbol::new("expr1")
+            ),
+            "Re-binding a fact should be reported."
+        );
+        let misordered_program = DynamicProgramBuilder::initialize()
+            .sorts(sorts)
+            .schedules(schedules)
+            .bindings(EgglogSymbols::default())
+            .finish();
+        assert!(
+            matches!(misordered_program, Err(BuildError::MissingRules)),
+            "The first ordering violation should be reported."
+        );
+    }
+}
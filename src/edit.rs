@@ -0,0 +1,190 @@
+use egglog::ast::{Command, Symbol};
+
+use crate::builder::fact_symbols;
+use crate::facts::get_fact_symbol;
+use crate::rules::get_rule_ruleset;
+use crate::schedule::without_ruleset;
+use crate::{DynamicProgramBuilder, EgglogFacts, EgglogProgram, EgglogRules, EgglogSchedules};
+
+impl EgglogProgram {
+    pub fn edit(&self) -> DynamicProgramBuilder {
+        DynamicProgramBuilder {
+            sorts: Some(self.sorts.clone()),
+            facts: Some(self.facts.clone()).filter(|facts| !facts.1.is_empty()),
+            rules: Some(self.rules.clone()).filter(|rules| !rules.is_empty()),
+            bindings: Some(self.bindings.clone()).filter(|bindings| !bindings.is_empty()),
+            schedules: Some(self.schedules.clone()).filter(|schedules| !schedules.is_empty()),
+            queries: Some(self.queries.clone()).filter(|queries| !queries.is_empty()),
+            binding_extraction: self.binding_extraction,
+            error: None,
+        }
+    }
+
+    pub fn remove_ruleset(&mut self, ruleset: Symbol) {
+        self.rules = self
+            .rules
+            .iter()
+            .map(|rules| {
+                EgglogRules::default().add_rules(
+                    rules
+                        .iter()
+                        .filter(|command| get_rule_ruleset(command) != Some(ruleset))
+                        .cloned(),
+                )
+            })
+            .filter(|rules| !rules.is_empty())
+            .collect();
+        self.schedules = self
+            .schedules
+            .iter()
+            .map(|schedules| {
+                EgglogSchedules::default().add_schedule(schedules.iter().filter_map(|command| {
+                    match command {
+                        Command::RunSchedule(schedule) => {
+                            without_ruleset(schedule, ruleset).map(Command::RunSchedule)
+                        }
+                        _ => Some(command.clone()),
+                    }
+                }))
+            })
+            .filter(|schedules| !schedules.is_empty())
+            .collect();
+    }
+
+    pub fn replace_schedules(&mut self, schedules: EgglogSchedules) {
+        self.schedules = vec![schedules];
+    }
+
+    pub fn retain_facts<Keep>(&mut self, mut keep: Keep)
+    where
+        Keep: FnMut(&Symbol, &Command) -> bool,
+    {
+        let retained_facts = EgglogFacts::default().add_facts(
+            self.facts
+                .1
+                .iter()
+                .filter(|fact| keep(&get_fact_symbol(fact), fact))
+                .cloned(),
+        );
+        self.facts = (fact_symbols(&retained_facts), retained_facts);
+        self.bindings
+            .retain(|binding| self.facts.0.contains(binding));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::{BuildError, EgglogSorts};
+
+    #[test]
+    fn edit_built_program() {
+        let (egglog_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(indoc! {"
+            (datatype Math (Num i64) (Add Math Math))
+            (let expr1 (Add (Num 1) (Num 2)))
+            (let expr2 (Num 3))
+            (ruleset comm-rules)
+            (rewrite (Add a b) (Add b a) :ruleset comm-rules)
+            (ruleset zero-rules)
+            (rewrite (Add a (Num 0)) a :ruleset zero-rules)
+            (run-schedule (seq (run comm-rules) (saturate (run zero-rules))))
+            (run-schedule (run comm-rules))
+        "})
+        .unwrap();
+        let mut edited_program = egglog_program
+            .edit()
+            .facts(EgglogFacts::default().add_facts_str("(let expr3 (Num 4))"))
+            .bindings([Symbol::new("expr1"), Symbol::new("expr2")].into())
+            .finish()
+            .unwrap();
+        assert_eq!(3, edited_program.facts().0.len());
+
+        edited_program.remove_ruleset(Symbol::new("comm-rules"));
+        assert_eq!(
+            2,
+            edited_program
+                .rules()
+                .iter()
+                .map(|rules| rules.len())
+                .sum::<usize>()
+        );
+        assert_eq!(
+            1,
+            edited_program
+                .schedules()
+                .iter()
+                .map(|schedules| schedules.len())
+                .sum::<usize>(),
+            "Schedules only running the removed ruleset should be dropped."
+        );
+        assert!(edited_program.validate().is_ok());
+        assert!(!edited_program.to_string().contains("comm-rules"));
+
+        edited_program.replace_schedules(
+            EgglogSchedules::default().add_schedule_str("(run-schedule (run zero-rules))"),
+        );
+        edited_program.retain_facts(|symbol, _fact| *symbol != Symbol::new("expr2"));
+        assert_eq!(2, edited_program.facts().0.len());
+        assert_eq!(1, edited_program.bindings().len());
+        assert!(!edited_program.to_string().contains("saturate"));
+    }
+
+    #[test]
+    fn edit_program_sorts_and_bindings() {
+        let (egglog_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(indoc! {"
+            (datatype Math (Num i64))
+            (let expr1 (Num 1))
+            (let expr2 (Num 2))
+        "})
+        .unwrap();
+        let egglog_program = egglog_program
+            .edit()
+            .bindings([Symbol::new("expr1")].into())
+            .finish()
+            .unwrap();
+        assert_eq!(
+            Err(BuildError::DuplicateBindings),
+            egglog_program
+                .edit()
+                .bindings([Symbol::new("expr2")].into())
+                .finish()
+                .map(|_program| ()),
+            "Bindings of an edited program are only changed by replacing them."
+        );
+        let edited_program = egglog_program
+            .edit()
+            .replace_sorts(
+                EgglogSorts::default().add_sort_str("(datatype Math (Num i64) (Neg Math))"),
+            )
+            .replace_bindings([Symbol::new("expr2")].into())
+            .finish()
+            .unwrap();
+        assert!(edited_program.sorts().0.contains(&Symbol::new("Neg")));
+        assert_eq!(
+            vec![Symbol::new("expr2")],
+            edited_program
+                .bindings()
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            "Replaced bindings should drop the previous ones."
+        );
+    }
+
+    #[test]
+    fn edit_program_without_facts() {
+        let (egglog_program, _unrouted_cmds) =
+            EgglogProgram::from_egglog_str("(datatype Math (Num i64))").unwrap();
+        let edited_program = egglog_program
+            .edit()
+            .bindings([Symbol::new("expr1")].into())
+            .finish();
+        assert_eq!(
+            Err(BuildError::MissingFacts),
+            edited_program.map(|_program| ()),
+            "An empty facts section should not satisfy bindings."
+        );
+    }
+}
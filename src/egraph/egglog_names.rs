@@ -1,5 +1,108 @@
 pub const EGGLOG_I64_SORT: &str = "i64";
 pub const EGGLOG_U64_SORT: &str = "u64";
 pub const EGGLOG_STRING_SORT: &str = "String";
+pub const EGGLOG_F64_SORT: &str = "f64";
+pub const EGGLOG_BOOL_SORT: &str = "bool";
+pub const EGGLOG_UNIT_SORT: &str = "Unit";
 pub const EGGLOG_VEC_SORT: &str = "Vec";
 pub const EGGLOG_VEC_OF_OP: &str = "vec-of";
+pub const EGGLOG_BUILTIN_SORTS: &[&str] = &[
+    EGGLOG_I64_SORT,
+    EGGLOG_U64_SORT,
+    EGGLOG_STRING_SORT,
+    EGGLOG_F64_SORT,
+    EGGLOG_BOOL_SORT,
+    EGGLOG_UNIT_SORT,
+    EGGLOG_VEC_SORT,
+    "Set",
+    "Map",
+    "Rational",
+    "BigInt",
+    "BigRat",
+    "MultiSet",
+    "UnstableFn",
+];
+pub const EGGLOG_DEFAULT_RULESET: &str = "";
+pub const EGGLOG_PRIMITIVES: &[&str] = &[
+    "+",
+    "-",
+    "*",
+    "/",
+    "%",
+    "&",
+    "|",
+    "^",
+    "<<",
+    ">>",
+    "<",
+    ">",
+    "<=",
+    ">=",
+    "!=",
+    "not-i64",
+    "min",
+    "max",
+    "log2",
+    "pow",
+    "exp",
+    "log",
+    "sqrt",
+    "floor",
+    "ceil",
+    "round",
+    "abs",
+    "neg",
+    "to-f64",
+    "to-i64",
+    "to-string",
+    "replace",
+    "count-matches",
+    "not",
+    "and",
+    "or",
+    "xor",
+    "=>",
+    "bool-=",
+    "bool-<",
+    "bool->",
+    "bool-<=",
+    "bool->=",
+    "bigint",
+    "bigrat",
+    "rational",
+    "numer",
+    "denom",
+    "vec-of",
+    "vec-empty",
+    "vec-append",
+    "vec-push",
+    "vec-pop",
+    "vec-get",
+    "vec-set",
+    "vec-remove",
+    "vec-length",
+    "vec-contains",
+    "vec-not-contains",
+    "set-of",
+    "set-empty",
+    "set-insert",
+    "set-remove",
+    "set-get",
+    "set-length",
+    "set-union",
+    "set-diff",
+    "set-intersect",
+    "set-contains",
+    "set-not-contains",
+    "map-empty",
+    "map-insert",
+    "map-get",
+    "map-remove",
+    "map-length",
+    "map-contains",
+    "map-not-contains",
+    "ordering-min",
+    "ordering-max",
+    "unstable-fn",
+    "unstable-app",
+];
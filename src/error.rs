@@ -0,0 +1,197 @@
+use std::fmt::Display;
+use std::path::PathBuf;
+
+use egglog::ast::{Command, ParseError, Span, Symbol};
+use itertools::Itertools;
+
+use crate::{CombineConflict, Diagnostics, EgglogSection, MergeStrategy, ValidationIssue};
+
+#[derive(Debug, Clone)]
+pub enum EgglogProgramError {
+    Parse {
+        span: Span,
+        message: String,
+    },
+    UnsupportedCommand {
+        section: EgglogSection,
+        command: Command,
+    },
+    SectionMismatch {
+        section: EgglogSection,
+        command: Command,
+    },
+    Io {
+        path: PathBuf,
+        message: String,
+    },
+    IncludeCycle {
+        cycle: Vec<PathBuf>,
+    },
+    Rejected(Diagnostics),
+    UnboundBindings {
+        symbols: Vec<Symbol>,
+    },
+    Egglog {
+        message: String,
+    },
+    Validation(Vec<ValidationIssue>),
+    CombineConflict(Vec<CombineConflict>),
+    RenameCapture {
+        captures: Vec<(Symbol, Symbol)>,
+    },
+    UnrepresentableValue {
+        sort: Symbol,
+        value: String,
+    },
+    Build(BuildError),
+    UnsupportedMergeStrategy {
+        section: EgglogSection,
+        strategy: MergeStrategy,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    MissingSorts { operation: &'static str },
+    DuplicateSorts,
+    MissingFacts,
+    DuplicateFact { symbol: Symbol },
+    DuplicateBindings,
+    MissingBindings,
+    MissingRules,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TermDecodeError {
+    UnknownConstructor {
+        sort: Symbol,
+        constructor: Symbol,
+    },
+    ArityMismatch {
+        constructor: Symbol,
+        expected: usize,
+        actual: usize,
+    },
+    UnexpectedTerm {
+        sort: Symbol,
+        term: String,
+    },
+}
+
+impl From<ParseError> for EgglogProgramError {
+    fn from(ParseError(span, message): ParseError) -> Self {
+        Self::Parse { span, message }
+    }
+}
+
+impl From<egglog::Error> for EgglogProgramError {
+    fn from(error: egglog::Error) -> Self {
+        Self::Egglog {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<BuildError> for EgglogProgramError {
+    fn from(error: BuildError) -> Self {
+        Self::Build(error)
+    }
+}
+
+impl Display for EgglogProgramError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse { span, message } => write!(f, "{}\nparse error: {}", span, message),
+            Self::UnsupportedCommand { section, command } => {
+                write!(
+                    f,
+                    "Egglog Command not supported in {}: {}",
+                    section, command
+                )
+            }
+            Self::SectionMismatch { section, command } => {
+                write!(
+                    f,
+                    "Egglog Command does not belong in {}: {}",
+                    section, command
+                )
+            }
+            Self::Io { path, message } => {
+                write!(f, "Failure to read {}: {}", path.display(), message)
+            }
+            Self::IncludeCycle { cycle } => write!(
+                f,
+                "Cyclic include: {}",
+                cycle.iter().map(|path| path.display()).join(" -> ")
+            ),
+            Self::Rejected(diagnostics) => write!(f, "{}", diagnostics),
+            Self::UnboundBindings { symbols } => write!(
+                f,
+                "Bindings are not let-bound in EgglogFacts: {}",
+                symbols.iter().join(" ")
+            ),
+            Self::Egglog { message } => write!(f, "Failure to run program: {}", message),
+            Self::Validation(issues) => write!(f, "{}", issues.iter().join("\n")),
+            Self::CombineConflict(conflicts) => write!(f, "{}", conflicts.iter().join("\n")),
+            Self::RenameCapture { captures } => write!(
+                f,
+                "Renaming would capture existing symbols: {}",
+                captures
+                    .iter()
+                    .map(|(symbol, renamed)| format!("{} -> {}", symbol, renamed))
+                    .join(", ")
+            ),
+            Self::UnrepresentableValue { sort, value } => {
+                write!(f, "Value {} cannot be written as an egglog {}", value, sort)
+            }
+            Self::Build(error) => write!(f, "{}", error),
+            Self::UnsupportedMergeStrategy { section, strategy } => {
+                write!(f, "{:?} cannot merge {}", strategy, section)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EgglogProgramError {}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingSorts { operation } => {
+                write!(f, "EgglogSorts must be defined before {}", operation)
+            }
+            Self::DuplicateSorts => write!(f, "EgglogSorts can only be defined once"),
+            Self::MissingFacts => write!(f, "Bindings require EgglogFacts"),
+            Self::DuplicateFact { symbol } => write!(f, "Fact {} is already let-bound", symbol),
+            Self::DuplicateBindings => write!(f, "Bindings can only be defined once"),
+            Self::MissingBindings => write!(f, "Extracting bindings requires bindings"),
+            Self::MissingRules => write!(f, "EgglogSchedules require EgglogRules"),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl Display for TermDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownConstructor { sort, constructor } => {
+                write!(f, "Unknown constructor {} for sort {}", constructor, sort)
+            }
+            Self::ArityMismatch {
+                constructor,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} expects {} arguments, got {}",
+                constructor, expected, actual
+            ),
+            Self::UnexpectedTerm { sort, term } => {
+                write!(f, "Term {} cannot be decoded as {}", term, sort)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TermDecodeError {}
@@ -0,0 +1,158 @@
+use std::rc::Rc;
+use std::sync::Arc;
+
+use egglog::ast::{Expr, GenericExpr, Literal, Symbol};
+
+use crate::egraph::egglog_names::{EGGLOG_I64_SORT, EGGLOG_VEC_OF_OP};
+use crate::{EgglogProgramError, DUMMY_SPAN};
+
+pub trait ToEgglogExpr {
+    fn try_to_egglog_expr(&self) -> Result<Expr, EgglogProgramError>;
+
+    fn to_egglog_expr(&self) -> Expr {
+        match self.try_to_egglog_expr() {
+            Ok(expr) => expr,
+            Err(error) => panic!("Failure to convert value to an egglog expr: {:?}", error),
+        }
+    }
+}
+
+// egglog types integer literals as i64, so unsigned values are stored in the
+// i64 sort as well and values outside its range cannot be represented.
+fn integer_literal<Value>(value: Value) -> Result<Expr, EgglogProgramError>
+where
+    Value: TryInto<i64> + ToString + Copy,
+{
+    match value.try_into() {
+        Ok(value) => Ok(GenericExpr::Lit(DUMMY_SPAN.clone(), Literal::Int(value))),
+        Err(_) => Err(EgglogProgramError::UnrepresentableValue {
+            sort: Symbol::from(EGGLOG_I64_SORT),
+            value: value.to_string(),
+        }),
+    }
+}
+
+macro_rules! integer_to_egglog_expr {
+    ($($integer:ty),+) => {
+        $(
+            impl ToEgglogExpr for $integer {
+                fn try_to_egglog_expr(&self) -> Result<Expr, EgglogProgramError> {
+                    integer_literal(*self)
+                }
+            }
+        )+
+    };
+}
+
+integer_to_egglog_expr!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl ToEgglogExpr for f64 {
+    fn try_to_egglog_expr(&self) -> Result<Expr, EgglogProgramError> {
+        Ok(GenericExpr::Lit(
+            DUMMY_SPAN.clone(),
+            Literal::Float((*self).into()),
+        ))
+    }
+}
+
+impl ToEgglogExpr for f32 {
+    fn try_to_egglog_expr(&self) -> Result<Expr, EgglogProgramError> {
+        f64::from(*self).try_to_egglog_expr()
+    }
+}
+
+impl ToEgglogExpr for bool {
+    fn try_to_egglog_expr(&self) -> Result<Expr, EgglogProgramError> {
+        Ok(GenericExpr::Lit(DUMMY_SPAN.clone(), Literal::Bool(*self)))
+    }
+}
+
+impl ToEgglogExpr for str {
+    fn try_to_egglog_expr(&self) -> Result<Expr, EgglogProgramError> {
+        Ok(GenericExpr::Lit(
+            DUMMY_SPAN.clone(),
+            Literal::String(Symbol::from(self)),
+        ))
+    }
+}
+
+impl ToEgglogExpr for String {
+    fn try_to_egglog_expr(&self) -> Result<Expr, EgglogProgramError> {
+        self.as_str().try_to_egglog_expr()
+    }
+}
+
+impl ToEgglogExpr for () {
+    fn try_to_egglog_expr(&self) -> Result<Expr, EgglogProgramError> {
+        Ok(GenericExpr::Lit(DUMMY_SPAN.clone(), Literal::Unit))
+    }
+}
+
+impl<Element: ToEgglogExpr> ToEgglogExpr for Vec<Element> {
+    fn try_to_egglog_expr(&self) -> Result<Expr, EgglogProgramError> {
+        Ok(GenericExpr::Call(
+            DUMMY_SPAN.clone(),
+            Symbol::from(EGGLOG_VEC_OF_OP),
+            self.iter()
+                .map(ToEgglogExpr::try_to_egglog_expr)
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+}
+
+impl<Value: ToEgglogExpr + ?Sized> ToEgglogExpr for Box<Value> {
+    fn try_to_egglog_expr(&self) -> Result<Expr, EgglogProgramError> {
+        (**self).try_to_egglog_expr()
+    }
+}
+
+impl<Value: ToEgglogExpr + ?Sized> ToEgglogExpr for Rc<Value> {
+    fn try_to_egglog_expr(&self) -> Result<Expr, EgglogProgramError> {
+        (**self).try_to_egglog_expr()
+    }
+}
+
+impl<Value: ToEgglogExpr + ?Sized> ToEgglogExpr for Arc<Value> {
+    fn try_to_egglog_expr(&self) -> Result<Expr, EgglogProgramError> {
+        (**self).try_to_egglog_expr()
+    }
+}
+
+impl<Value: ToEgglogExpr + ?Sized> ToEgglogExpr for &Value {
+    fn try_to_egglog_expr(&self) -> Result<Expr, EgglogProgramError> {
+        (**self).try_to_egglog_expr()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_primitives_to_exprs() {
+        assert_eq!("-3", (-3i64).to_egglog_expr().to_string());
+        assert_eq!("7", 7u8.to_egglog_expr().to_string());
+        assert_eq!("\"dfg\"", String::from("dfg").to_egglog_expr().to_string());
+        assert_eq!(
+            "(vec-of 1 2 3)",
+            vec![1i32, 2, 3].to_egglog_expr().to_string(),
+            "Vectors should be built with vec-of."
+        );
+    }
+
+    #[test]
+    fn reject_unrepresentable_u64() {
+        assert_eq!(
+            "9223372036854775807",
+            (i64::MAX as u64).to_egglog_expr().to_string()
+        );
+        assert!(
+            matches!(
+                u64::MAX.try_to_egglog_expr(),
+                Err(EgglogProgramError::UnrepresentableValue { sort, .. })
+                    if sort == Symbol::from(EGGLOG_I64_SORT)
+            ),
+            "u64 values above i64::MAX have no egglog literal."
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use derive_getters::Getters;
+use egglog::ast::{Expr, Symbol};
+use egglog::{EGraph, Term, TermDag};
+use indexmap::IndexMap;
+
+use crate::{EgglogProgram, EgglogProgramError, DUMMY_SPAN};
+
+pub type EgglogExtractions = IndexMap<Symbol, ExtractionResult>;
+
+#[derive(Debug, Clone, Getters)]
+pub struct ExtractionResult {
+    termdag: TermDag,
+    term: Term,
+    cost: usize,
+    variants: Vec<Term>,
+}
+
+impl ExtractionResult {
+    pub fn term_string(&self) -> String {
+        self.termdag.to_string(&self.term)
+    }
+
+    pub fn variant_strings(&self) -> Vec<String> {
+        self.variants
+            .iter()
+            .map(|variant| self.termdag.to_string(variant))
+            .collect()
+    }
+}
+
+impl EgglogProgram {
+    pub fn extract_bound_terms(
+        &self,
+        egraph: &mut EGraph,
+        variants: usize,
+    ) -> Result<EgglogExtractions, EgglogProgramError> {
+        let mut extractions = EgglogExtractions::default();
+        for binding in self.bindings() {
+            let (sort, value) = egraph.eval_expr(&Expr::Var(DUMMY_SPAN.clone(), *binding))?;
+            let mut termdag = TermDag::default();
+            let (cost, term) = egraph.extract(value, &mut termdag, &sort)?;
+            let variant_terms = if variants > 0 {
+                egraph.extract_variants(&sort, value, variants, &mut termdag)
+            } else {
+                vec![]
+            };
+            extractions.insert(
+                *binding,
+                ExtractionResult {
+                    termdag,
+                    term,
+                    cost,
+                    variants: variant_terms,
+                },
+            );
+        }
+        Ok(extractions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::{EgglogFacts, EgglogProgramBuilder, EgglogSorts};
+
+    #[test]
+    fn extract_terms_for_bound_symbols() {
+        let sorts =
+            EgglogSorts::default().add_sort_str("(datatype Math (Num i64) (Add Math Math))");
+        let facts = EgglogFacts::default().add_facts_str(indoc! {"
+            (let expr1 (Add (Num 1) (Num 2)))
+            (let expr2 (Num 3))
+        "});
+        let egglog_program = EgglogProgramBuilder::initialize()
+            .sorts(sorts)
+            .facts(facts)
+            .bindings([Symbol::new("expr1"), Symbol::new("expr2")].into())
+            .try_variables()
+            .unwrap();
+        let mut egraph = EGraph::default();
+        egglog_program.run(&mut egraph).unwrap();
+        let extractions = egglog_program.extract_bound_terms(&mut egraph, 2).unwrap();
+        assert_eq!(2, extractions.len(), "Every binding should be extracted.");
+        assert_eq!(
+            "(Add (Num 1) (Num 2))",
+            extractions[&Symbol::new("expr1")].term_string()
+        );
+        assert_eq!("(Num 3)", extractions[&Symbol::new("expr2")].term_string());
+        assert!(*extractions[&Symbol::new("expr2")].cost() > 0);
+    }
+}
@@ -1,20 +1,46 @@
 use std::ops::Deref;
+use std::path::Path;
 
 use egglog::ast::{Command, GenericAction, Symbol};
 use itertools::Itertools;
 
-use crate::EgglogCommandList;
+use crate::parse::{parse_egglog_file, parse_egglog_str};
+use crate::program::try_section_commands;
+use crate::{
+    Diagnostics, EgglogCommandList, EgglogProgramError, EgglogSection, ToEgglogExpr, DUMMY_SPAN,
+};
 
-pub(crate) fn get_fact_symbol(command: &Command) -> Symbol {
+pub(crate) fn is_fact_command(command: &Command) -> bool {
+    matches!(*command, Command::Action(..))
+}
+
+fn is_let_command(command: &Command) -> bool {
+    matches!(*command, Command::Action(GenericAction::Let(..)))
+}
+
+pub(crate) fn try_get_fact_symbol(command: &Command) -> Result<Symbol, EgglogProgramError> {
     match command {
         Command::Action(let_action) => {
             if let GenericAction::Let(__span, let_stmt_symbol, _let_stmt) = let_action {
-                *let_stmt_symbol
+                Ok(*let_stmt_symbol)
             } else {
-                panic!("Egglog Command not supported in EgglogFacts {:?}.", command)
+                Err(EgglogProgramError::UnsupportedCommand {
+                    section: EgglogSection::Facts,
+                    command: command.clone(),
+                })
             }
         }
-        _ => panic!("Egglog Command not supported in EgglogFacts {:?}.", command),
+        _ => Err(EgglogProgramError::SectionMismatch {
+            section: EgglogSection::Facts,
+            command: command.clone(),
+        }),
+    }
+}
+
+pub(crate) fn get_fact_symbol(command: &Command) -> Symbol {
+    match try_get_fact_symbol(command) {
+        Ok(fact_symbol) => fact_symbol,
+        Err(_) => panic!("Egglog Command not supported in EgglogFacts {:?}.", command),
     }
 }
 
@@ -26,22 +52,91 @@ impl EgglogFacts {
     where
         CommandList: IntoIterator<Item = Command>,
     {
-        let mut facts = fact_list
-            .into_iter()
-            .filter(|command| matches!(*command, Command::Action(..)))
-            .collect_vec();
+        let mut facts = fact_list.into_iter().filter(is_fact_command).collect_vec();
         let mut updated_facts = Self(self.0);
         updated_facts.0.append(&mut facts);
         updated_facts
     }
 
+    pub fn try_add_let<Name, Value>(
+        self,
+        name: Name,
+        value: &Value,
+    ) -> Result<Self, EgglogProgramError>
+    where
+        Name: Into<Symbol>,
+        Value: ToEgglogExpr + ?Sized,
+    {
+        let let_action =
+            GenericAction::Let(DUMMY_SPAN.clone(), name.into(), value.try_to_egglog_expr()?);
+        Ok(Self::add_facts(self, [Command::Action(let_action)]))
+    }
+
+    pub fn add_let<Name, Value>(self, name: Name, value: &Value) -> Self
+    where
+        Name: Into<Symbol>,
+        Value: ToEgglogExpr + ?Sized,
+    {
+        match Self::try_add_let(self, name, value) {
+            Ok(facts) => facts,
+            Err(error) => panic!("Failure to build facts from value: {:?}", error),
+        }
+    }
+
+    pub fn add_facts_checked<CommandList>(self, fact_list: CommandList) -> (Self, Diagnostics)
+    where
+        CommandList: IntoIterator<Item = Command>,
+    {
+        let (accepted, rejected): (EgglogCommandList, EgglogCommandList) =
+            fact_list.into_iter().partition(is_fact_command);
+        (
+            Self::add_facts(self, accepted),
+            Diagnostics::from_rejected(EgglogSection::Facts, rejected),
+        )
+    }
+
+    pub fn try_add_facts_str(self, fact_str: &str) -> Result<Self, EgglogProgramError> {
+        let fact_commands = try_section_commands(
+            EgglogSection::Facts,
+            parse_egglog_str(None, fact_str)?,
+            is_let_command,
+        )?;
+        Ok(Self::add_facts(self, fact_commands))
+    }
+
     pub fn add_facts_str(self, fact_str: &str) -> Self {
-        let mut egglog_parser = egglog::ast::Parser::default();
-        match egglog_parser.get_program_from_string(None, fact_str) {
+        match parse_egglog_str(None, fact_str) {
             Ok(fact_commands) => Self::add_facts(self, fact_commands),
             Err(error) => panic!("Failure to build facts from string: {:?}", error),
         }
     }
+
+    pub fn add_facts_str_checked(
+        self,
+        fact_str: &str,
+    ) -> Result<(Self, Diagnostics), EgglogProgramError> {
+        let fact_commands = parse_egglog_str(None, fact_str)?;
+        Ok(Self::add_facts_checked(self, fact_commands))
+    }
+
+    pub fn try_add_facts_file<P: AsRef<Path>>(
+        self,
+        fact_path: P,
+    ) -> Result<Self, EgglogProgramError> {
+        let fact_commands = try_section_commands(
+            EgglogSection::Facts,
+            parse_egglog_file(fact_path)?,
+            is_let_command,
+        )?;
+        Ok(Self::add_facts(self, fact_commands))
+    }
+
+    pub fn add_facts_file<P: AsRef<Path>>(self, fact_path: P) -> Self {
+        match parse_egglog_file(fact_path) {
+            Ok(fact_commands) => Self::add_facts(self, fact_commands),
+            Err(error) => panic!("Failure to build facts from file: {:?}", error),
+        }
+    }
 }
 
 impl Deref for EgglogFacts {
@@ -79,6 +174,7 @@ impl IntoIterator for EgglogFacts {
 
 #[cfg(test)]
 mod tests {
+    use indoc::indoc;
 
     use crate::DUMMY_SPAN;
     use egglog::ast::{Action, Expr, GenericCommand, Literal, Symbol};
@@ -99,4 +195,63 @@ mod tests {
             "There should be 1 commands present, one for each action stmt."
         );
     }
+
+    #[test]
+    fn try_create_egglog_facts_from_malformed_str() {
+        let facts_result = EgglogFacts::default().try_add_facts_str("(let var1 (Num 1)))");
+        assert!(
+            matches!(facts_result, Err(EgglogProgramError::Parse { .. })),
+            "Unbalanced parentheses should be reported as a parse error."
+        );
+    }
+
+    #[test]
+    fn try_create_egglog_facts_from_non_let_action() {
+        let facts_result = EgglogFacts::default().try_add_facts_str(indoc! {"
+            (let var1 (Num 1))
+            (union var1 (Num 2))
+        "});
+        assert!(
+            matches!(
+                facts_result,
+                Err(EgglogProgramError::UnsupportedCommand {
+                    section: EgglogSection::Facts,
+                    ..
+                })
+            ),
+            "Only let actions bind facts."
+        );
+        let egglog_facts = EgglogFacts::default().add_facts_str(indoc! {"
+            (let var1 (Num 1))
+            (union var1 (Num 2))
+        "});
+        assert_eq!(
+            2,
+            egglog_facts.len(),
+            "The infallible API should keep every action."
+        );
+    }
+
+    #[test]
+    fn add_let_from_rust_values() {
+        let egglog_facts = EgglogFacts::default()
+            .add_let("width", &32i64)
+            .add_let("ports", &vec![String::from("a"), String::from("b")]);
+        assert_eq!(
+            vec![
+                "(let width 32)".to_owned(),
+                "(let ports (vec-of \"a\" \"b\"))".to_owned()
+            ],
+            egglog_facts
+                .iter()
+                .map(|command| command.to_string())
+                .collect_vec(),
+            "Rust values should be converted into let actions."
+        );
+        assert_eq!(
+            Symbol::new("ports"),
+            get_fact_symbol(&egglog_facts[1]),
+            "The let binding should use the given name."
+        );
+    }
 }
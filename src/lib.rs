@@ -1,3 +1,6 @@
+extern crate self as egglog_program;
+
+pub use egglog;
 pub mod builder;
 pub use builder::*;
 pub mod egraph;
@@ -5,14 +8,52 @@ pub mod egraph;
 pub mod program;
 pub use egraph::*;
 pub use program::*;
+pub mod combine;
+pub use combine::{CombineConflict, MergePolicy, MergeStrategy};
+pub mod datatype;
+pub use datatype::EgglogDatatype;
+#[cfg(feature = "derive")]
+pub use egglog_program_derive::EgglogDatatype;
+pub mod diagnostics;
+pub mod dynamic;
+mod edit;
+pub use diagnostics::Diagnostics;
+pub use dynamic::DynamicProgramBuilder;
+pub mod error;
+pub use error::{BuildError, EgglogProgramError, TermDecodeError};
+pub mod expr;
+#[cfg(feature = "derive")]
+pub use egglog_program_derive::ToEgglogExpr;
+pub use expr::ToEgglogExpr;
+pub mod extract;
+pub use extract::{EgglogExtractions, ExtractionResult};
 pub mod facts;
+mod parse;
+mod pretty;
+pub mod queries;
+pub mod rename;
+pub use rename::EgglogRenames;
+pub mod rule_builder;
+pub use rule_builder::{BiRewriteBuilder, RewriteBuilder, RuleBuilder};
 pub mod rules;
+pub mod run;
 pub mod schedule;
+pub mod schedule_builder;
+pub use schedule_builder::ScheduleBuilder;
 pub mod sorts;
+pub mod term;
+mod typecheck;
+pub mod validate;
+#[cfg(feature = "derive")]
+pub use egglog_program_derive::FromEgglogTerm;
 pub use facts::EgglogFacts;
+pub use queries::EgglogQueries;
 pub use rules::EgglogRules;
+pub use run::RunReport;
 pub use schedule::EgglogSchedules;
 pub use sorts::EgglogSorts;
+pub use term::FromEgglogTerm;
+pub use validate::ValidationIssue;
 
 #[cfg(test)]
 mod tests {
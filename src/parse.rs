@@ -0,0 +1,174 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use egglog::ast::Command;
+
+use crate::{EgglogCommandList, EgglogProgramError};
+
+pub(crate) fn parse_egglog_str(
+    filename: Option<String>,
+    egglog_str: &str,
+) -> Result<EgglogCommandList, EgglogProgramError> {
+    let mut egglog_parser = egglog::ast::Parser::default();
+    Ok(egglog_parser.get_program_from_string(filename, egglog_str)?)
+}
+
+pub(crate) fn parse_egglog_file<P: AsRef<Path>>(
+    egglog_path: P,
+) -> Result<EgglogCommandList, EgglogProgramError> {
+    let mut include_stack = Vec::new();
+    parse_egglog_file_with_includes(egglog_path.as_ref(), &mut include_stack)
+}
+
+fn parse_egglog_file_with_includes(
+    egglog_path: &Path,
+    include_stack: &mut Vec<PathBuf>,
+) -> Result<EgglogCommandList, EgglogProgramError> {
+    let io_error = |error: std::io::Error| EgglogProgramError::Io {
+        path: egglog_path.to_path_buf(),
+        message: error.to_string(),
+    };
+    let canonical_path = egglog_path.canonicalize().map_err(io_error)?;
+    if let Some(cycle_start) = include_stack
+        .iter()
+        .position(|path| *path == canonical_path)
+    {
+        let mut cycle = include_stack[cycle_start..].to_vec();
+        cycle.push(canonical_path);
+        return Err(EgglogProgramError::IncludeCycle { cycle });
+    }
+    let egglog_str = fs::read_to_string(&canonical_path).map_err(io_error)?;
+    let commands = parse_egglog_str(Some(egglog_path.display().to_string()), &egglog_str)?;
+
+    include_stack.push(canonical_path.clone());
+    let mut resolved_commands = EgglogCommandList::default();
+    for command in commands {
+        match command {
+            Command::Include(_span, include_file) => {
+                let include_path = match canonical_path.parent() {
+                    Some(include_dir) => include_dir.join(include_file),
+                    None => PathBuf::from(include_file),
+                };
+                resolved_commands.append(&mut parse_egglog_file_with_includes(
+                    &include_path,
+                    include_stack,
+                )?);
+            }
+            command => resolved_commands.push(command),
+        }
+    }
+    include_stack.pop();
+    Ok(resolved_commands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::command_span;
+    use crate::{EgglogFacts, EgglogQueries, EgglogRules, EgglogSchedules, EgglogSorts};
+
+    fn write_egglog_files(test_dir: &str, files: &[(&str, &str)]) -> PathBuf {
+        // Tests run in parallel, possibly from several test binaries at once.
+        let egglog_dir = std::env::temp_dir().join(format!("{}-{}", test_dir, std::process::id()));
+        let _ = fs::remove_dir_all(&egglog_dir);
+        fs::create_dir_all(egglog_dir.join("lib")).unwrap();
+        for (file_name, contents) in files {
+            fs::write(egglog_dir.join(file_name), contents).unwrap();
+        }
+        egglog_dir
+    }
+
+    #[test]
+    fn parse_egglog_file_resolves_includes() {
+        let egglog_dir = write_egglog_files(
+            "egglog-program-parse-includes",
+            &[
+                (
+                    "main.egg",
+                    "(include \"lib/math.egg\")\n(let expr1 (Num 1))",
+                ),
+                (
+                    "lib/math.egg",
+                    "(include \"num.egg\")\n(datatype Op (Neg Math))",
+                ),
+                ("lib/num.egg", "(datatype Math (Num i64))"),
+            ],
+        );
+        let commands = parse_egglog_file(egglog_dir.join("main.egg")).unwrap();
+        assert_eq!(
+            3,
+            commands.len(),
+            "Includes should be replaced by the commands of the included files."
+        );
+        assert!(matches!(commands[0], Command::Datatype { .. }));
+        assert!(matches!(commands[1], Command::Datatype { .. }));
+        assert!(matches!(commands[2], Command::Action(..)));
+    }
+
+    #[test]
+    fn parse_egglog_file_detects_include_cycle() {
+        let egglog_dir = write_egglog_files(
+            "egglog-program-parse-include-cycle",
+            &[
+                ("main.egg", "(include \"lib/a.egg\")"),
+                ("lib/a.egg", "(include \"b.egg\")"),
+                ("lib/b.egg", "(include \"a.egg\")"),
+            ],
+        );
+        let parse_result = parse_egglog_file(egglog_dir.join("main.egg"));
+        assert!(
+            matches!(parse_result, Err(EgglogProgramError::IncludeCycle { ref cycle }) if cycle.len() == 3),
+            "Cyclic includes should be reported instead of recursing forever."
+        );
+    }
+
+    #[test]
+    fn add_section_files_keep_file_names_in_spans() {
+        let egglog_dir = write_egglog_files(
+            "egglog-program-parse-section-files",
+            &[
+                ("sorts.egg", "(datatype Math (Num i64) (Add Math Math))"),
+                ("facts.egg", "(let expr1 (Add (Num 1) (Num 2)))"),
+                (
+                    "rules.egg",
+                    "(ruleset math-rules)\n(rewrite (Add a b) (Add b a) :ruleset math-rules)",
+                ),
+                ("schedules.egg", "(run-schedule (run math-rules))"),
+                ("queries.egg", "(check (= expr1 (Add (Num 2) (Num 1))))"),
+            ],
+        );
+        let sorts = EgglogSorts::default().add_sort_file(egglog_dir.join("sorts.egg"));
+        let facts = EgglogFacts::default().add_facts_file(egglog_dir.join("facts.egg"));
+        let rules = EgglogRules::default().add_rule_file(egglog_dir.join("rules.egg"));
+        let schedules =
+            EgglogSchedules::default().add_schedule_file(egglog_dir.join("schedules.egg"));
+        let queries = EgglogQueries::default().add_queries_file(egglog_dir.join("queries.egg"));
+        let spanned_commands = [
+            ("sorts.egg", sorts.first()),
+            ("facts.egg", facts.first()),
+            ("rules.egg", rules.last()),
+            ("schedules.egg", schedules.first()),
+            ("queries.egg", queries.first()),
+        ];
+        for (file_name, command) in spanned_commands {
+            let span = command.and_then(command_span).map(|span| span.to_string());
+            assert!(
+                span.as_deref().is_some_and(|span| span.contains(file_name)),
+                "Commands loaded from {} should carry the file name in their span, got {:?}.",
+                file_name,
+                span
+            );
+        }
+    }
+
+    #[test]
+    fn try_add_section_file_reports_missing_file() {
+        let egglog_dir = write_egglog_files("egglog-program-parse-missing-file", &[]);
+        let missing_path = egglog_dir.join("missing.egg");
+        let facts = EgglogFacts::default().try_add_facts_file(&missing_path);
+        assert!(
+            matches!(facts, Err(EgglogProgramError::Io { ref path, .. }) if *path == missing_path),
+            "A missing file should be reported with its path."
+        );
+    }
+}
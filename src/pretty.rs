@@ -0,0 +1,150 @@
+use egglog::ast::Command;
+use itertools::Itertools;
+
+const EGGLOG_LINE_WIDTH: usize = 80;
+const EGGLOG_INDENT_WIDTH: usize = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Sexp {
+    Atom(String),
+    List(Vec<Sexp>),
+}
+
+impl Sexp {
+    fn flat(&self) -> String {
+        match self {
+            Sexp::Atom(atom) => atom.clone(),
+            Sexp::List(items) => format!("({})", items.iter().map(Sexp::flat).join(" ")),
+        }
+    }
+}
+
+fn push_sexp(stack: &mut [Vec<Sexp>], sexp: Sexp) {
+    if let Some(items) = stack.last_mut() {
+        items.push(sexp);
+    }
+}
+
+fn parse_sexps(source: &str) -> Vec<Sexp> {
+    let mut stack: Vec<Vec<Sexp>> = vec![vec![]];
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => stack.push(vec![]),
+            ')' if stack.len() > 1 => {
+                let items = stack.pop().unwrap_or_default();
+                push_sexp(&mut stack, Sexp::List(items));
+            }
+            ';' => while chars.next_if(|next| *next != '\n').is_some() {},
+            '"' => {
+                let mut atom = String::from(c);
+                while let Some(next) = chars.next() {
+                    atom.push(next);
+                    if next == '\\' {
+                        if let Some(escaped) = chars.next() {
+                            atom.push(escaped);
+                        }
+                    } else if next == '"' {
+                        break;
+                    }
+                }
+                push_sexp(&mut stack, Sexp::Atom(atom));
+            }
+            c if c.is_whitespace() || c == ')' => {}
+            c => {
+                let mut atom = String::from(c);
+                while let Some(next) =
+                    chars.next_if(|next| !next.is_whitespace() && *next != '(' && *next != ')')
+                {
+                    atom.push(next);
+                }
+                push_sexp(&mut stack, Sexp::Atom(atom));
+            }
+        }
+    }
+    while stack.len() > 1 {
+        let items = stack.pop().unwrap_or_default();
+        push_sexp(&mut stack, Sexp::List(items));
+    }
+    stack.pop().unwrap_or_default()
+}
+
+fn write_sexp(sexp: &Sexp, indent: usize, output: &mut String) {
+    let flat_sexp = sexp.flat();
+    let items = match sexp {
+        Sexp::List(items) if !items.is_empty() && indent + flat_sexp.len() > EGGLOG_LINE_WIDTH => {
+            items
+        }
+        _ => {
+            output.push_str(&flat_sexp);
+            return;
+        }
+    };
+
+    let header_len = items
+        .iter()
+        .take_while(|item| matches!(item, Sexp::Atom(..)))
+        .count()
+        .max(1);
+    output.push('(');
+    output.push_str(&items[..header_len].iter().map(Sexp::flat).join(" "));
+    let child_indent = indent + EGGLOG_INDENT_WIDTH;
+    let mut children = items[header_len..].iter();
+    while let Some(child) = children.next() {
+        output.push('\n');
+        output.push_str(&" ".repeat(child_indent));
+        match child {
+            Sexp::Atom(keyword) if keyword.starts_with(':') => {
+                output.push_str(keyword);
+                if let Some(keyword_value) = children.next() {
+                    output.push(' ');
+                    write_sexp(keyword_value, child_indent + keyword.len() + 1, output);
+                }
+            }
+            _ => write_sexp(child, child_indent, output),
+        }
+    }
+    output.push(')');
+}
+
+pub(crate) fn pretty_egglog_command(command: &Command) -> String {
+    parse_sexps(&command.to_string())
+        .iter()
+        .map(|sexp| {
+            let mut output = String::new();
+            write_sexp(sexp, 0, &mut output);
+            output
+        })
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_egglog_str;
+
+    #[test]
+    fn pretty_print_long_command_over_lines() {
+        let datatype_str = "(datatype LLHDDFG (ValueRef i64) (ConstInt i64 String) (Add i64 LLHDDFG LLHDDFG) (Sub i64 LLHDDFG LLHDDFG))";
+        let commands = parse_egglog_str(None, datatype_str).unwrap();
+        let pretty_datatype = pretty_egglog_command(&commands[0]);
+        assert_eq!(
+            "(datatype LLHDDFG\n  (ValueRef i64)\n  (ConstInt i64 String)\n  (Add i64 LLHDDFG LLHDDFG)\n  (Sub i64 LLHDDFG LLHDDFG))",
+            pretty_datatype
+        );
+        let reparsed_commands = parse_egglog_str(None, &pretty_datatype).unwrap();
+        assert_eq!(commands[0].to_string(), reparsed_commands[0].to_string());
+    }
+
+    #[test]
+    fn pretty_print_keeps_strings_and_keywords_intact() {
+        let sexps = parse_sexps(
+            "(rule ((= e (Var \"a (b) c\"))) ((union e (Num 0))) :ruleset r) ; trailing",
+        );
+        assert_eq!(1, sexps.len());
+        assert_eq!(
+            "(rule ((= e (Var \"a (b) c\"))) ((union e (Num 0))) :ruleset r)",
+            sexps[0].flat()
+        );
+    }
+}
@@ -1,13 +1,83 @@
-use crate::facts::EgglogFacts;
-use crate::rules::EgglogRules;
-use crate::schedule::EgglogSchedules;
-use crate::sorts::EgglogSorts;
+use crate::facts::{is_fact_command, EgglogFacts};
+use crate::queries::{is_query_command, EgglogQueries};
+use crate::rules::{is_rule_command, EgglogRules};
+use crate::schedule::{is_schedule_command, EgglogSchedules};
+use crate::sorts::{is_sort_command, EgglogSorts};
+use crate::EgglogProgramError;
 use egglog::ast::{Command, Symbol};
 use indexmap::IndexSet;
+use std::fmt::Display;
 
 pub type EgglogProgramSorts = (EgglogSymbols, EgglogSorts);
 pub type EgglogProgramFacts = (EgglogSymbols, EgglogFacts);
 pub type EgglogRuleList = Vec<EgglogRules>;
 pub type EgglogScheduleList = Vec<EgglogSchedules>;
+pub type EgglogQueryList = Vec<EgglogQueries>;
 pub type EgglogCommandList = Vec<Command>;
 pub type EgglogSymbols = IndexSet<Symbol>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EgglogSection {
+    Sorts,
+    Facts,
+    Rules,
+    Schedules,
+    Queries,
+}
+
+impl Display for EgglogSection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sorts => write!(f, "EgglogSorts"),
+            Self::Facts => write!(f, "EgglogFacts"),
+            Self::Rules => write!(f, "EgglogRules"),
+            Self::Schedules => write!(f, "EgglogSchedules"),
+            Self::Queries => write!(f, "EgglogQueries"),
+        }
+    }
+}
+
+impl EgglogSection {
+    pub(crate) fn of_command(command: &Command) -> Option<Self> {
+        if is_sort_command(command) {
+            Some(Self::Sorts)
+        } else if is_fact_command(command) {
+            Some(Self::Facts)
+        } else if is_rule_command(command) {
+            Some(Self::Rules)
+        } else if is_schedule_command(command) {
+            Some(Self::Schedules)
+        } else if is_query_command(command) {
+            Some(Self::Queries)
+        } else {
+            None
+        }
+    }
+}
+
+// Commands owned by another section are a `SectionMismatch`; commands no
+// section owns, or which the section owns but cannot hold, are unsupported.
+pub(crate) fn try_section_commands<Accepts>(
+    section: EgglogSection,
+    commands: EgglogCommandList,
+    accepts: Accepts,
+) -> Result<EgglogCommandList, EgglogProgramError>
+where
+    Accepts: Fn(&Command) -> bool,
+{
+    match commands.iter().find(|command| !accepts(command)) {
+        Some(command) => match EgglogSection::of_command(command) {
+            Some(command_section) if command_section != section => {
+                Err(EgglogProgramError::SectionMismatch {
+                    section,
+                    command: command.clone(),
+                })
+            }
+            _ => Err(EgglogProgramError::UnsupportedCommand {
+                section,
+                command: command.clone(),
+            }),
+        },
+        None => Ok(commands),
+    }
+}
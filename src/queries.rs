@@ -0,0 +1,154 @@
+use std::ops::Deref;
+use std::path::Path;
+
+use egglog::ast::Command;
+use itertools::Itertools;
+
+use crate::parse::{parse_egglog_file, parse_egglog_str};
+use crate::program::try_section_commands;
+use crate::{Diagnostics, EgglogCommandList, EgglogProgramError, EgglogSection};
+
+pub(crate) fn is_query_command(command: &Command) -> bool {
+    matches!(*command, Command::Check(..))
+        || matches!(*command, Command::QueryExtract { .. })
+        || matches!(*command, Command::PrintFunction(..))
+        || matches!(*command, Command::PrintSize(..))
+        || matches!(*command, Command::PrintOverallStatistics)
+        || matches!(*command, Command::SetOption { .. })
+        || matches!(*command, Command::Output { .. })
+        || matches!(*command, Command::Fail(..))
+        || matches!(*command, Command::Push(..))
+        || matches!(*command, Command::Pop(..))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EgglogQueries(EgglogCommandList);
+
+impl EgglogQueries {
+    pub fn add_queries<CommandList>(self, query_list: CommandList) -> Self
+    where
+        CommandList: IntoIterator<Item = Command>,
+    {
+        let mut queries = query_list
+            .into_iter()
+            .filter(is_query_command)
+            .collect_vec();
+        let mut updated_queries = Self(self.0);
+        updated_queries.0.append(&mut queries);
+        updated_queries
+    }
+
+    pub fn add_queries_checked<CommandList>(self, query_list: CommandList) -> (Self, Diagnostics)
+    where
+        CommandList: IntoIterator<Item = Command>,
+    {
+        let (accepted, rejected): (EgglogCommandList, EgglogCommandList) =
+            query_list.into_iter().partition(is_query_command);
+        (
+            Self::add_queries(self, accepted),
+            Diagnostics::from_rejected(EgglogSection::Queries, rejected),
+        )
+    }
+
+    pub fn try_add_queries_str(self, query_str: &str) -> Result<Self, EgglogProgramError> {
+        let query_commands = try_section_commands(
+            EgglogSection::Queries,
+            parse_egglog_str(None, query_str)?,
+            is_query_command,
+        )?;
+        Ok(Self::add_queries(self, query_commands))
+    }
+
+    pub fn add_queries_str(self, query_str: &str) -> Self {
+        match parse_egglog_str(None, query_str) {
+            Ok(query_commands) => Self::add_queries(self, query_commands),
+            Err(error) => panic!("Failure to build queries from string: {:?}", error),
+        }
+    }
+
+    pub fn add_queries_str_checked(
+        self,
+        query_str: &str,
+    ) -> Result<(Self, Diagnostics), EgglogProgramError> {
+        let query_commands = parse_egglog_str(None, query_str)?;
+        Ok(Self::add_queries_checked(self, query_commands))
+    }
+
+    pub fn try_add_queries_file<P: AsRef<Path>>(
+        self,
+        query_path: P,
+    ) -> Result<Self, EgglogProgramError> {
+        let query_commands = try_section_commands(
+            EgglogSection::Queries,
+            parse_egglog_file(query_path)?,
+            is_query_command,
+        )?;
+        Ok(Self::add_queries(self, query_commands))
+    }
+
+    pub fn add_queries_file<P: AsRef<Path>>(self, query_path: P) -> Self {
+        match parse_egglog_file(query_path) {
+            Ok(query_commands) => Self::add_queries(self, query_commands),
+            Err(error) => panic!("Failure to build queries from file: {:?}", error),
+        }
+    }
+}
+
+impl Deref for EgglogQueries {
+    type Target = EgglogCommandList;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<EgglogCommandList> AsRef<EgglogCommandList> for EgglogQueries
+where
+    EgglogCommandList: ?Sized,
+    <Self as Deref>::Target: AsRef<EgglogCommandList>,
+{
+    fn as_ref(&self) -> &EgglogCommandList {
+        self.deref().as_ref()
+    }
+}
+
+impl From<EgglogQueries> for EgglogCommandList {
+    fn from(queries: EgglogQueries) -> Self {
+        queries.0
+    }
+}
+
+impl IntoIterator for EgglogQueries {
+    type Item = Command;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn create_egglog_queries_from_str() {
+        let query_str = indoc! {"
+            (datatype Math (Num i64))
+            (let expr1 (Num 1))
+            (check (= expr1 (Num 1)))
+            (extract expr1)
+            (print-function Num 10)
+            (print-size Num)
+        "};
+        let egglog_queries = EgglogQueries::default().add_queries_str(query_str);
+        assert_eq!(
+            4,
+            egglog_queries.len(),
+            "There should be 4 commands present, one for each query."
+        );
+        assert!(matches!(egglog_queries[0], Command::Check(..)));
+    }
+}
@@ -0,0 +1,571 @@
+use std::cell::RefCell;
+
+use egglog::ast::{
+    Action, Command, Expr, Fact, GenericAction, GenericExpr, GenericFact, GenericSchedule,
+    Schedule, Subdatatypes, Symbol,
+};
+use indexmap::IndexMap;
+
+use crate::egraph::egglog_names::{EGGLOG_BUILTIN_SORTS, EGGLOG_DEFAULT_RULESET};
+use crate::rules::get_rule_ruleset;
+use crate::validate::{expr_vars, fact_exprs, is_egglog_primitive};
+use crate::{
+    EgglogCommandList, EgglogFacts, EgglogProgram, EgglogProgramError, EgglogQueries, EgglogRules,
+    EgglogSchedules, EgglogSorts, EgglogSymbols,
+};
+
+pub type EgglogRenames = IndexMap<Symbol, Symbol>;
+
+fn query_locals(facts: &[Fact], globals: &EgglogSymbols) -> EgglogSymbols {
+    let mut locals = EgglogSymbols::default();
+    facts
+        .iter()
+        .flat_map(fact_exprs)
+        .for_each(|expr| expr_vars(expr, &mut locals));
+    locals.retain(|var| !globals.contains(var));
+    locals
+}
+
+pub(crate) fn rule_locals(command: &Command, globals: &EgglogSymbols) -> EgglogSymbols {
+    let mut locals = EgglogSymbols::default();
+    match command {
+        Command::Rule { rule, .. } => {
+            rule.body
+                .iter()
+                .flat_map(fact_exprs)
+                .for_each(|expr| expr_vars(expr, &mut locals));
+            for action in rule.head.0.iter() {
+                if let GenericAction::Let(_span, var, _expr) = action {
+                    locals.insert(*var);
+                }
+            }
+        }
+        Command::Rewrite(_ruleset, rewrite, _subsume) | Command::BiRewrite(_ruleset, rewrite) => {
+            expr_vars(&rewrite.lhs, &mut locals);
+            rewrite
+                .conditions
+                .iter()
+                .flat_map(fact_exprs)
+                .for_each(|expr| expr_vars(expr, &mut locals));
+        }
+        Command::Check(_span, facts) => locals = query_locals(facts, globals),
+        _ => {}
+    }
+    locals.retain(|var| !globals.contains(var));
+    locals
+}
+
+pub(crate) struct SymbolRenamer<'a> {
+    renames: &'a EgglogRenames,
+    globals: &'a EgglogSymbols,
+    rename_vars: bool,
+    occurrences: RefCell<EgglogSymbols>,
+}
+
+impl<'a> SymbolRenamer<'a> {
+    pub(crate) fn new(
+        renames: &'a EgglogRenames,
+        globals: &'a EgglogSymbols,
+        rename_vars: bool,
+    ) -> Self {
+        Self {
+            renames,
+            globals,
+            rename_vars,
+            occurrences: RefCell::default(),
+        }
+    }
+
+    fn rename(&self, symbol: &mut Symbol) {
+        self.occurrences.borrow_mut().insert(*symbol);
+        if let Some(renamed) = self.renames.get(symbol) {
+            *symbol = *renamed;
+        }
+    }
+
+    fn rename_var(&self, var: &mut Symbol, locals: &EgglogSymbols) {
+        self.occurrences.borrow_mut().insert(*var);
+        if self.rename_vars || !locals.contains(var) {
+            self.rename(var);
+        }
+    }
+
+    fn rename_expr(&self, expr: &mut Expr, locals: &EgglogSymbols) {
+        match expr {
+            GenericExpr::Lit(..) => {}
+            GenericExpr::Var(_span, var) => self.rename_var(var, locals),
+            GenericExpr::Call(_span, head, args) => {
+                self.rename(head);
+                args.iter_mut()
+                    .for_each(|arg| self.rename_expr(arg, locals));
+            }
+        }
+    }
+
+    fn rename_fact(&self, fact: &mut Fact, locals: &EgglogSymbols) {
+        match fact {
+            GenericFact::Eq(_span, exprs) => exprs
+                .iter_mut()
+                .for_each(|expr| self.rename_expr(expr, locals)),
+            GenericFact::Fact(expr) => self.rename_expr(expr, locals),
+        }
+    }
+
+    fn rename_action(&self, action: &mut Action, locals: &EgglogSymbols) {
+        match action {
+            GenericAction::Let(_span, var, expr) => {
+                self.rename_var(var, locals);
+                self.rename_expr(expr, locals);
+            }
+            GenericAction::Set(_span, head, args, value) => {
+                self.rename(head);
+                args.iter_mut()
+                    .for_each(|arg| self.rename_expr(arg, locals));
+                self.rename_expr(value, locals);
+            }
+            GenericAction::Change(_span, _change, head, args) => {
+                self.rename(head);
+                args.iter_mut()
+                    .for_each(|arg| self.rename_expr(arg, locals));
+            }
+            GenericAction::Union(_span, lhs, rhs) | GenericAction::Extract(_span, lhs, rhs) => {
+                self.rename_expr(lhs, locals);
+                self.rename_expr(rhs, locals);
+            }
+            GenericAction::Panic(..) => {}
+            GenericAction::Expr(_span, expr) => self.rename_expr(expr, locals),
+        }
+    }
+
+    fn rename_schedule(&self, schedule: &mut Schedule) {
+        match schedule {
+            GenericSchedule::Saturate(_span, schedule)
+            | GenericSchedule::Repeat(_span, _, schedule) => self.rename_schedule(schedule),
+            GenericSchedule::Run(_span, run_config) => {
+                self.rename(&mut run_config.ruleset);
+                if let Some(until_facts) = run_config.until.as_mut() {
+                    let locals = query_locals(until_facts, self.globals);
+                    until_facts
+                        .iter_mut()
+                        .for_each(|fact| self.rename_fact(fact, &locals));
+                }
+            }
+            GenericSchedule::Sequence(_span, schedules) => schedules
+                .iter_mut()
+                .for_each(|schedule| self.rename_schedule(schedule)),
+        }
+    }
+
+    pub(crate) fn rename_command(&self, command: &mut Command) {
+        let locals = rule_locals(command, self.globals);
+        match command {
+            Command::Sort(_span, name, presort) => {
+                self.rename(name);
+                if let Some((_presort_name, args)) = presort.as_mut() {
+                    args.iter_mut()
+                        .for_each(|arg| self.rename_expr(arg, &locals));
+                }
+            }
+            Command::Datatype { name, variants, .. } => {
+                self.rename(name);
+                for variant in variants.iter_mut() {
+                    self.rename(&mut variant.name);
+                    variant.types.iter_mut().for_each(|sort| self.rename(sort));
+                }
+            }
+            Command::Datatypes { datatypes, .. } => {
+                for (_span, name, subdatatypes) in datatypes.iter_mut() {
+                    self.rename(name);
+                    match subdatatypes {
+                        Subdatatypes::Variants(variants) => {
+                            for variant in variants.iter_mut() {
+                                self.rename(&mut variant.name);
+                                variant.types.iter_mut().for_each(|sort| self.rename(sort));
+                            }
+                        }
+                        Subdatatypes::NewSort(_presort, args) => args
+                            .iter_mut()
+                            .for_each(|arg| self.rename_expr(arg, &locals)),
+                    }
+                }
+            }
+            Command::Relation { name, inputs, .. } => {
+                self.rename(name);
+                inputs.iter_mut().for_each(|sort| self.rename(sort));
+            }
+            Command::Function { name, schema, .. } | Command::Constructor { name, schema, .. } => {
+                self.rename(name);
+                schema.input.iter_mut().for_each(|sort| self.rename(sort));
+                self.rename(&mut schema.output);
+            }
+            Command::Action(action) => self.rename_action(action, &locals),
+            Command::AddRuleset(ruleset) => self.rename(ruleset),
+            Command::Rule { ruleset, rule, .. } => {
+                self.rename(ruleset);
+                rule.body
+                    .iter_mut()
+                    .for_each(|fact| self.rename_fact(fact, &locals));
+                rule.head
+                    .0
+                    .iter_mut()
+                    .for_each(|action| self.rename_action(action, &locals));
+            }
+            Command::Rewrite(ruleset, rewrite, _subsume) | Command::BiRewrite(ruleset, rewrite) => {
+                self.rename(ruleset);
+                self.rename_expr(&mut rewrite.lhs, &locals);
+                self.rename_expr(&mut rewrite.rhs, &locals);
+                rewrite
+                    .conditions
+                    .iter_mut()
+                    .for_each(|fact| self.rename_fact(fact, &locals));
+            }
+            Command::RunSchedule(schedule) => self.rename_schedule(schedule),
+            Command::Check(_span, facts) => facts
+                .iter_mut()
+                .for_each(|fact| self.rename_fact(fact, &locals)),
+            Command::QueryExtract { expr, .. } => self.rename_expr(expr, &locals),
+            Command::PrintFunction(_span, name, ..) => self.rename(name),
+            Command::PrintSize(_span, name) => {
+                if let Some(name) = name.as_mut() {
+                    self.rename(name);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn rename_commands<'c>(
+        &self,
+        commands: impl IntoIterator<Item = &'c Command>,
+    ) -> EgglogCommandList {
+        commands
+            .into_iter()
+            .map(|command| {
+                let mut renamed_command = command.clone();
+                self.rename_command(&mut renamed_command);
+                renamed_command
+            })
+            .collect()
+    }
+
+    fn rename_symbols(&self, symbols: &EgglogSymbols) -> EgglogSymbols {
+        symbols
+            .iter()
+            .map(|symbol| *self.renames.get(symbol).unwrap_or(symbol))
+            .collect()
+    }
+}
+
+pub(crate) fn occurring_symbols<'c>(
+    commands: impl IntoIterator<Item = &'c Command>,
+    globals: &EgglogSymbols,
+) -> EgglogSymbols {
+    let renames = EgglogRenames::default();
+    let renamer = SymbolRenamer::new(&renames, globals, false);
+    renamer.rename_commands(commands);
+    renamer.occurrences.into_inner()
+}
+
+pub(crate) fn check_renames(
+    renames: &EgglogRenames,
+    occurrences: &EgglogSymbols,
+) -> Result<(), EgglogProgramError> {
+    let captures = renames
+        .iter()
+        .filter(|(symbol, renamed)| symbol != renamed)
+        .filter(|(_symbol, renamed)| {
+            (occurrences.contains(*renamed) && !renames.contains_key(*renamed))
+                || renames.values().filter(|target| target == renamed).count() > 1
+                || is_egglog_primitive(renamed)
+                || EGGLOG_BUILTIN_SORTS.contains(&renamed.as_str())
+        })
+        .map(|(symbol, renamed)| (*symbol, *renamed))
+        .collect::<Vec<_>>();
+    if captures.is_empty() {
+        Ok(())
+    } else {
+        Err(EgglogProgramError::RenameCapture { captures })
+    }
+}
+
+fn rename_section<'c>(
+    commands: impl IntoIterator<Item = &'c Command> + Clone,
+    renames: &EgglogRenames,
+    globals: &EgglogSymbols,
+    rename_vars: bool,
+) -> Result<EgglogCommandList, EgglogProgramError> {
+    check_renames(renames, &occurring_symbols(commands.clone(), globals))?;
+    Ok(SymbolRenamer::new(renames, globals, rename_vars).rename_commands(commands))
+}
+
+impl EgglogSorts {
+    pub fn rename_symbols(&mut self, renames: &EgglogRenames) -> Result<(), EgglogProgramError> {
+        let globals = EgglogSymbols::default();
+        *self = Self::default().add_sorts(rename_section(self.iter(), renames, &globals, false)?);
+        Ok(())
+    }
+}
+
+impl EgglogFacts {
+    pub fn rename_symbols(&mut self, renames: &EgglogRenames) -> Result<(), EgglogProgramError> {
+        let globals = EgglogSymbols::default();
+        *self = Self::default().add_facts(rename_section(self.iter(), renames, &globals, false)?);
+        Ok(())
+    }
+}
+
+// Rules, schedules and queries may refer to let-bound facts, so callers pass the
+// global bindings to tell them apart from pattern variables.
+impl EgglogRules {
+    pub fn rename_symbols(
+        &mut self,
+        renames: &EgglogRenames,
+        globals: &EgglogSymbols,
+    ) -> Result<(), EgglogProgramError> {
+        *self = Self::default().add_rules(rename_section(self.iter(), renames, globals, false)?);
+        Ok(())
+    }
+
+    pub fn rename_symbols_and_vars(
+        &mut self,
+        renames: &EgglogRenames,
+        globals: &EgglogSymbols,
+    ) -> Result<(), EgglogProgramError> {
+        *self = Self::default().add_rules(rename_section(self.iter(), renames, globals, true)?);
+        Ok(())
+    }
+}
+
+impl EgglogSchedules {
+    pub fn rename_symbols(
+        &mut self,
+        renames: &EgglogRenames,
+        globals: &EgglogSymbols,
+    ) -> Result<(), EgglogProgramError> {
+        *self = Self::default().add_schedule(rename_section(self.iter(), renames, globals, false)?);
+        Ok(())
+    }
+}
+
+impl EgglogQueries {
+    pub fn rename_symbols(
+        &mut self,
+        renames: &EgglogRenames,
+        globals: &EgglogSymbols,
+    ) -> Result<(), EgglogProgramError> {
+        *self = Self::default().add_queries(rename_section(self.iter(), renames, globals, false)?);
+        Ok(())
+    }
+}
+
+impl EgglogProgram {
+    pub(crate) fn apply_renames(&mut self, renames: &EgglogRenames, rename_vars: bool) {
+        let globals = self.facts.0.clone();
+        let renamer = SymbolRenamer::new(renames, &globals, rename_vars);
+        self.sorts = (
+            renamer.rename_symbols(&self.sorts.0),
+            EgglogSorts::default().add_sorts(renamer.rename_commands(self.sorts.1.iter())),
+        );
+        self.facts = (
+            renamer.rename_symbols(&self.facts.0),
+            EgglogFacts::default().add_facts(renamer.rename_commands(self.facts.1.iter())),
+        );
+        self.rules = self
+            .rules
+            .iter()
+            .map(|rules| EgglogRules::default().add_rules(renamer.rename_commands(rules.iter())))
+            .collect();
+        self.schedules = self
+            .schedules
+            .iter()
+            .map(|schedules| {
+                EgglogSchedules::default().add_schedule(renamer.rename_commands(schedules.iter()))
+            })
+            .collect();
+        self.queries = self
+            .queries
+            .iter()
+            .map(|queries| {
+                EgglogQueries::default().add_queries(renamer.rename_commands(queries.iter()))
+            })
+            .collect();
+        self.bindings = renamer.rename_symbols(&self.bindings);
+    }
+
+    pub fn rename_symbols(&mut self, renames: &EgglogRenames) -> Result<(), EgglogProgramError> {
+        let occurrences = occurring_symbols(&EgglogCommandList::from(self.clone()), &self.facts.0);
+        check_renames(renames, &occurrences)?;
+        self.apply_renames(renames, false);
+        Ok(())
+    }
+
+    pub fn rename_symbols_and_vars(
+        &mut self,
+        renames: &EgglogRenames,
+    ) -> Result<(), EgglogProgramError> {
+        let occurrences = occurring_symbols(&EgglogCommandList::from(self.clone()), &self.facts.0);
+        check_renames(renames, &occurrences)?;
+        self.apply_renames(renames, true);
+        Ok(())
+    }
+
+    pub fn namespaced(&self, prefix: &str) -> Self {
+        self.namespaced_except(prefix, &EgglogSymbols::default())
+    }
+
+    pub fn namespaced_except(&self, prefix: &str, exposed: &EgglogSymbols) -> Self {
+        let rulesets = self
+            .rules
+            .iter()
+            .flat_map(|rules| rules.iter())
+            .filter_map(get_rule_ruleset);
+        let renames: EgglogRenames = self
+            .sorts
+            .0
+            .iter()
+            .chain(self.facts.0.iter())
+            .copied()
+            .chain(rulesets)
+            .filter(|symbol| {
+                !exposed.contains(symbol)
+                    && !is_egglog_primitive(symbol)
+                    && !EGGLOG_BUILTIN_SORTS.contains(&symbol.as_str())
+                    && symbol.as_str() != EGGLOG_DEFAULT_RULESET
+            })
+            .map(|symbol| (symbol, Symbol::from(format!("{}.{}", prefix, symbol))))
+            .collect();
+        let mut namespaced_program = self.clone();
+        namespaced_program.apply_renames(&renames, false);
+        namespaced_program
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::parse::parse_egglog_str;
+    use crate::EgglogProgramBuilder;
+
+    #[test]
+    fn rename_globals_but_not_pattern_vars() {
+        let mut commands = parse_egglog_str(
+            None,
+            "(rule ((= e (Add x expr1))) ((union e (Num x))) :ruleset math-rules)",
+        )
+        .unwrap();
+        let renames: EgglogRenames = [
+            (Symbol::new("Add"), Symbol::new("Plus")),
+            (Symbol::new("expr1"), Symbol::new("base-expr1")),
+            (Symbol::new("x"), Symbol::new("y")),
+            (Symbol::new("math-rules"), Symbol::new("base-rules")),
+        ]
+        .into();
+        let globals: EgglogSymbols = [Symbol::new("expr1")].into();
+        let renamer = SymbolRenamer::new(&renames, &globals, false);
+        renamer.rename_command(&mut commands[0]);
+        let renamed_rule = commands[0].to_string();
+        assert!(renamed_rule.contains("(Plus x base-expr1)"));
+        assert!(renamed_rule.contains("(Num x)"));
+        assert!(renamed_rule.contains("base-rules"));
+
+        let mut egglog_queries =
+            EgglogQueries::default().add_queries_str("(check (= expr1 (Add x (Num 1))))");
+        egglog_queries.rename_symbols(&renames, &globals).unwrap();
+        assert!(
+            egglog_queries[0]
+                .to_string()
+                .contains("(= base-expr1 (Plus x (Num 1)))"),
+            "Query variables should keep their names unless vars are renamed."
+        );
+        let mut egglog_schedules = EgglogSchedules::default()
+            .add_schedule_str("(run-schedule (run math-rules :until (= expr1 (Add x (Num 1)))))");
+        egglog_schedules.rename_symbols(&renames, &globals).unwrap();
+        assert!(egglog_schedules[0]
+            .to_string()
+            .contains("(= base-expr1 (Plus x (Num 1)))"));
+
+        let mut egglog_rules = EgglogRules::default()
+            .add_rule_str("(rule ((= e (Add x expr1))) ((union e (Num x))) :ruleset math-rules)");
+        egglog_rules.rename_symbols(&renames, &globals).unwrap();
+        assert_eq!(
+            renamed_rule,
+            egglog_rules[0].to_string(),
+            "Section renames should agree with the program-level rename."
+        );
+    }
+
+    #[test]
+    fn namespace_program_symbols() {
+        let (egglog_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(indoc! {"
+            (datatype Math (Num i64) (Add Math Math))
+            (sort MathVec (Vec Math))
+            (let expr1 (Add (Num 1) (Num 2)))
+            (ruleset math-rules)
+            (rewrite (Add a b) (Add b a) :ruleset math-rules)
+            (run-schedule (saturate (run math-rules)))
+        "})
+        .unwrap();
+        let egglog_program = EgglogProgramBuilder::initialize()
+            .sorts(egglog_program.sorts().1.clone())
+            .facts(egglog_program.facts().1.clone())
+            .rules(egglog_program.rules()[0].clone())
+            .bindings([Symbol::new("expr1")].into())
+            .schedules(egglog_program.schedules()[0].clone())
+            .program();
+        let exposed: EgglogSymbols = [Symbol::new("Num")].into();
+        let namespaced_program = egglog_program.namespaced_except("team", &exposed);
+        let namespaced_source = namespaced_program.to_string();
+        assert!(namespaced_source
+            .contains("(datatype team.Math (Num i64) (team.Add team.Math team.Math))"));
+        assert!(namespaced_source.contains("(sort team.MathVec (Vec team.Math))"));
+        assert!(namespaced_source.contains("(team.Add b a)"));
+        assert!(namespaced_source.contains("(run team.math-rules)"));
+        assert!(namespaced_program
+            .bindings()
+            .contains(&Symbol::new("team.expr1")));
+        assert!(namespaced_program.validate().is_ok());
+    }
+
+    #[test]
+    fn rename_symbols_refuses_capture() {
+        let (mut egglog_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(indoc! {"
+            (datatype Math (Num i64) (Add Math Math) (Plus Math Math))
+            (let expr1 (Add (Num 1) (Num 2)))
+            (ruleset math-rules)
+            (rule ((= e (Add x y))) ((union e (Add y x))) :ruleset math-rules)
+        "})
+        .unwrap();
+        let capturing_renames: EgglogRenames = [(Symbol::new("Add"), Symbol::new("Plus"))].into();
+        assert!(matches!(
+            egglog_program.clone().rename_symbols(&capturing_renames),
+            Err(EgglogProgramError::RenameCapture { .. })
+        ));
+        let builtin_renames: EgglogRenames = [
+            (Symbol::new("Add"), Symbol::new("i64")),
+            (Symbol::new("Plus"), Symbol::new("vec-push")),
+        ]
+        .into();
+        assert!(matches!(
+            egglog_program.clone().rename_symbols(&builtin_renames),
+            Err(EgglogProgramError::RenameCapture { captures }) if captures.len() == 2
+        ));
+        let capturing_vars: EgglogRenames = [(Symbol::new("x"), Symbol::new("y"))].into();
+        assert!(egglog_program
+            .clone()
+            .rename_symbols_and_vars(&capturing_vars)
+            .is_err());
+
+        let renames: EgglogRenames = [
+            (Symbol::new("Add"), Symbol::new("Sum")),
+            (Symbol::new("x"), Symbol::new("lhs")),
+        ]
+        .into();
+        egglog_program.rename_symbols_and_vars(&renames).unwrap();
+        let renamed_source = egglog_program.to_string();
+        assert!(renamed_source.contains("(Sum (Num 1) (Num 2))"));
+        assert!(renamed_source.contains("(union e (Sum y lhs))"));
+        assert!(egglog_program.sorts().0.contains(&Symbol::new("Sum")));
+    }
+}
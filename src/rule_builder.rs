@@ -0,0 +1,275 @@
+use egglog::ast::{
+    Action, Command, Expr, Fact, GenericAction, GenericActions, GenericExpr, GenericFact,
+    GenericRewrite, GenericRule, Symbol,
+};
+
+use crate::egraph::egglog_names::EGGLOG_DEFAULT_RULESET;
+use crate::rules::{declared_rulesets, get_rule_ruleset};
+use crate::{EgglogRules, DUMMY_SPAN};
+
+pub fn var<Name: Into<Symbol>>(name: Name) -> Expr {
+    GenericExpr::Var(DUMMY_SPAN.clone(), name.into())
+}
+
+pub fn call<Head, Args>(head: Head, args: Args) -> Expr
+where
+    Head: Into<Symbol>,
+    Args: IntoIterator<Item = Expr>,
+{
+    GenericExpr::Call(DUMMY_SPAN.clone(), head.into(), args.into_iter().collect())
+}
+
+pub fn eq(lhs: Expr, rhs: Expr) -> Fact {
+    GenericFact::Eq(DUMMY_SPAN.clone(), vec![lhs, rhs])
+}
+
+#[derive(Debug, Clone)]
+pub struct RuleBuilder {
+    ruleset: Symbol,
+    name: Symbol,
+    query: Vec<Fact>,
+    actions: Vec<Action>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RewriteBuilder {
+    ruleset: Symbol,
+    lhs: Expr,
+    rhs: Expr,
+    conditions: Vec<Fact>,
+    subsume: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct BiRewriteBuilder {
+    ruleset: Symbol,
+    lhs: Expr,
+    rhs: Expr,
+    conditions: Vec<Fact>,
+}
+
+impl RuleBuilder {
+    pub fn rule() -> Self {
+        Self {
+            ruleset: Symbol::from(EGGLOG_DEFAULT_RULESET),
+            name: Symbol::from(""),
+            query: vec![],
+            actions: vec![],
+        }
+    }
+
+    pub fn rewrite(lhs: Expr, rhs: Expr) -> RewriteBuilder {
+        RewriteBuilder {
+            ruleset: Symbol::from(EGGLOG_DEFAULT_RULESET),
+            lhs,
+            rhs,
+            conditions: vec![],
+            subsume: false,
+        }
+    }
+
+    pub fn birewrite(lhs: Expr, rhs: Expr) -> BiRewriteBuilder {
+        BiRewriteBuilder {
+            ruleset: Symbol::from(EGGLOG_DEFAULT_RULESET),
+            lhs,
+            rhs,
+            conditions: vec![],
+        }
+    }
+
+    pub fn ruleset<Ruleset: Into<Symbol>>(self, ruleset: Ruleset) -> Self {
+        Self {
+            ruleset: ruleset.into(),
+            ..self
+        }
+    }
+
+    pub fn name<Name: Into<Symbol>>(self, name: Name) -> Self {
+        Self {
+            name: name.into(),
+            ..self
+        }
+    }
+
+    pub fn query(mut self, fact: Fact) -> Self {
+        self.query.push(fact);
+        self
+    }
+
+    pub fn action(mut self, action: Action) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    pub fn union(self, lhs: Expr, rhs: Expr) -> Self {
+        self.action(GenericAction::Union(DUMMY_SPAN.clone(), lhs, rhs))
+    }
+
+    pub fn set<Head, Args>(self, head: Head, args: Args, value: Expr) -> Self
+    where
+        Head: Into<Symbol>,
+        Args: IntoIterator<Item = Expr>,
+    {
+        self.action(GenericAction::Set(
+            DUMMY_SPAN.clone(),
+            head.into(),
+            args.into_iter().collect(),
+            value,
+        ))
+    }
+
+    pub fn build(self) -> Command {
+        Command::Rule {
+            name: self.name,
+            ruleset: self.ruleset,
+            rule: GenericRule {
+                span: DUMMY_SPAN.clone(),
+                head: GenericActions(self.actions),
+                body: self.query,
+            },
+        }
+    }
+}
+
+impl RewriteBuilder {
+    pub fn ruleset<Ruleset: Into<Symbol>>(self, ruleset: Ruleset) -> Self {
+        Self {
+            ruleset: ruleset.into(),
+            ..self
+        }
+    }
+
+    pub fn when(mut self, fact: Fact) -> Self {
+        self.conditions.push(fact);
+        self
+    }
+
+    pub fn subsume(self) -> Self {
+        Self {
+            subsume: true,
+            ..self
+        }
+    }
+
+    pub fn build(self) -> Command {
+        let rewrite = GenericRewrite {
+            span: DUMMY_SPAN.clone(),
+            lhs: self.lhs,
+            rhs: self.rhs,
+            conditions: self.conditions,
+        };
+        Command::Rewrite(self.ruleset, rewrite, self.subsume)
+    }
+}
+
+impl BiRewriteBuilder {
+    pub fn ruleset<Ruleset: Into<Symbol>>(self, ruleset: Ruleset) -> Self {
+        Self {
+            ruleset: ruleset.into(),
+            ..self
+        }
+    }
+
+    pub fn when(mut self, fact: Fact) -> Self {
+        self.conditions.push(fact);
+        self
+    }
+
+    pub fn build(self) -> Command {
+        let rewrite = GenericRewrite {
+            span: DUMMY_SPAN.clone(),
+            lhs: self.lhs,
+            rhs: self.rhs,
+            conditions: self.conditions,
+        };
+        Command::BiRewrite(self.ruleset, rewrite)
+    }
+}
+
+impl From<RuleBuilder> for Command {
+    fn from(rule_builder: RuleBuilder) -> Self {
+        rule_builder.build()
+    }
+}
+
+impl From<RewriteBuilder> for Command {
+    fn from(rewrite_builder: RewriteBuilder) -> Self {
+        rewrite_builder.build()
+    }
+}
+
+impl From<BiRewriteBuilder> for Command {
+    fn from(birewrite_builder: BiRewriteBuilder) -> Self {
+        birewrite_builder.build()
+    }
+}
+
+impl EgglogRules {
+    pub fn add_rule<Rule: Into<Command>>(self, rule: Rule) -> Self {
+        let rule_command = rule.into();
+        let ruleset = get_rule_ruleset(&rule_command);
+        let declared =
+            ruleset.is_none_or(|ruleset| declared_rulesets(self.iter()).contains(&ruleset));
+        let mut rule_commands = vec![];
+        if !declared {
+            rule_commands.extend(ruleset.map(Command::AddRuleset));
+        }
+        rule_commands.push(rule_command);
+        self.add_rules(rule_commands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::ToEgglogExpr;
+
+    #[test]
+    fn build_rules_into_named_ruleset() {
+        let egglog_rules = EgglogRules::default()
+            .add_rule(
+                RuleBuilder::rewrite(
+                    call("Add", [var("a"), call("Num", [0i64.to_egglog_expr()])]),
+                    var("a"),
+                )
+                .ruleset("math-rules")
+                .subsume(),
+            )
+            .add_rule(
+                RuleBuilder::birewrite(
+                    call("Add", [var("a"), var("b")]),
+                    call("Add", [var("b"), var("a")]),
+                )
+                .ruleset("math-rules"),
+            )
+            .add_rule(
+                RuleBuilder::rule()
+                    .ruleset("math-rules")
+                    .query(eq(var("e"), call("Mul", [var("x"), var("y")])))
+                    .union(var("e"), call("Mul", [var("y"), var("x")]))
+                    .set("cost", [var("e")], 2i64.to_egglog_expr()),
+            );
+        let expected_rules = EgglogRules::default().add_rule_str(indoc! {"
+            (ruleset math-rules)
+            (rewrite (Add a (Num 0)) a :subsume :ruleset math-rules)
+            (birewrite (Add a b) (Add b a) :ruleset math-rules)
+            (rule ((= e (Mul x y)))
+                  ((union e (Mul y x)) (set (cost e) 2))
+                  :ruleset math-rules)
+        "});
+        assert_eq!(
+            expected_rules
+                .iter()
+                .map(|command| command.to_string())
+                .collect_vec(),
+            egglog_rules
+                .iter()
+                .map(|command| command.to_string())
+                .collect_vec(),
+            "The ruleset should be declared once, before its first rule."
+        );
+    }
+}
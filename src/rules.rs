@@ -1,9 +1,44 @@
 use std::ops::Deref;
+use std::path::Path;
 
-use egglog::ast::Command;
+use egglog::ast::{Command, Symbol};
 use itertools::Itertools;
 
-use crate::EgglogCommandList;
+use crate::egraph::egglog_names::EGGLOG_DEFAULT_RULESET;
+use crate::parse::{parse_egglog_file, parse_egglog_str};
+use crate::program::try_section_commands;
+use crate::{Diagnostics, EgglogCommandList, EgglogProgramError, EgglogSection, EgglogSymbols};
+
+pub(crate) fn is_rule_command(command: &Command) -> bool {
+    matches!(*command, Command::AddRuleset(..))
+        || matches!(*command, Command::Rule { .. })
+        || matches!(*command, Command::Rewrite { .. })
+        || matches!(*command, Command::BiRewrite { .. })
+}
+
+pub(crate) fn get_rule_ruleset(command: &Command) -> Option<Symbol> {
+    match command {
+        Command::AddRuleset(ruleset) => Some(*ruleset),
+        Command::Rule { ruleset, .. } => Some(*ruleset),
+        Command::Rewrite(ruleset, _rewrite, _subsume) => Some(*ruleset),
+        Command::BiRewrite(ruleset, _rewrite) => Some(*ruleset),
+        _ => None,
+    }
+}
+
+pub(crate) fn declared_rulesets<'c, CommandList>(rule_list: CommandList) -> EgglogSymbols
+where
+    CommandList: IntoIterator<Item = &'c Command>,
+{
+    rule_list
+        .into_iter()
+        .filter_map(|command| match command {
+            Command::AddRuleset(ruleset) => Some(*ruleset),
+            _ => None,
+        })
+        .chain([Symbol::from(EGGLOG_DEFAULT_RULESET)])
+        .collect()
+}
 
 #[derive(Debug, Clone, Default)]
 pub struct EgglogRules(EgglogCommandList);
@@ -17,25 +52,67 @@ impl EgglogRules {
     {
         let mut rulesets = ruleset_names
             .into_iter()
-            .filter(|command| {
-                matches!(*command, Command::AddRuleset(..))
-                    || matches!(*command, Command::Rule { .. })
-                    || matches!(*command, Command::Rewrite { .. })
-                    || matches!(*command, Command::BiRewrite { .. })
-            })
+            .filter(is_rule_command)
             .collect_vec();
         let mut updated_rulesets = Self(self.0);
         updated_rulesets.0.append(&mut rulesets);
         updated_rulesets
     }
 
+    pub fn add_rules_checked<CommandList>(self, rule_list: CommandList) -> (Self, Diagnostics)
+    where
+        CommandList: IntoIterator<Item = Command>,
+    {
+        let (accepted, rejected): (EgglogCommandList, EgglogCommandList) =
+            rule_list.into_iter().partition(is_rule_command);
+        (
+            Self::add_rules(self, accepted),
+            Diagnostics::from_rejected(EgglogSection::Rules, rejected),
+        )
+    }
+
+    pub fn try_add_rule_str(self, rule_str: &str) -> Result<Self, EgglogProgramError> {
+        let rule_commands = try_section_commands(
+            EgglogSection::Rules,
+            parse_egglog_str(None, rule_str)?,
+            is_rule_command,
+        )?;
+        Ok(Self::add_rules(self, rule_commands))
+    }
+
     pub fn add_rule_str(self, rule_str: &str) -> Self {
-        let mut egglog_parser = egglog::ast::Parser::default();
-        match egglog_parser.get_program_from_string(None, rule_str) {
+        match parse_egglog_str(None, rule_str) {
             Ok(rule_commands) => Self::add_rules(self, rule_commands),
             Err(error) => panic!("Failure to build rules from string: {:?}", error),
         }
     }
+
+    pub fn add_rule_str_checked(
+        self,
+        rule_str: &str,
+    ) -> Result<(Self, Diagnostics), EgglogProgramError> {
+        let rule_commands = parse_egglog_str(None, rule_str)?;
+        Ok(Self::add_rules_checked(self, rule_commands))
+    }
+
+    pub fn try_add_rule_file<P: AsRef<Path>>(
+        self,
+        rule_path: P,
+    ) -> Result<Self, EgglogProgramError> {
+        let rule_commands = try_section_commands(
+            EgglogSection::Rules,
+            parse_egglog_file(rule_path)?,
+            is_rule_command,
+        )?;
+        Ok(Self::add_rules(self, rule_commands))
+    }
+
+    pub fn add_rule_file<P: AsRef<Path>>(self, rule_path: P) -> Self {
+        match parse_egglog_file(rule_path) {
+            Ok(rule_commands) => Self::add_rules(self, rule_commands),
+            Err(error) => panic!("Failure to build rules from file: {:?}", error),
+        }
+    }
 }
 
 impl Deref for EgglogRules {
@@ -117,4 +194,44 @@ mod tests {
             assert_eq!("div-ext", rule_symbol.as_str(), "Rule name does not match.");
         }
     }
+
+    #[test]
+    fn try_create_egglog_rules_from_malformed_str() {
+        let rules_result =
+            EgglogRules::default().try_add_rule_str("(ruleset div-ext) (rewrite (Div a b)");
+        assert!(
+            matches!(rules_result, Err(EgglogProgramError::Parse { .. })),
+            "Unbalanced parentheses should be reported as a parse error."
+        );
+    }
+
+    #[test]
+    fn try_create_egglog_rules_from_other_sections() {
+        let rule_str = "(ruleset div-ext) (datatype Math (Num i64))";
+        assert!(
+            matches!(
+                EgglogRules::default().try_add_rule_str(rule_str),
+                Err(EgglogProgramError::SectionMismatch {
+                    section: EgglogSection::Rules,
+                    command: GenericCommand::Datatype { .. },
+                })
+            ),
+            "Sort commands should be reported as belonging to another section."
+        );
+        assert!(
+            matches!(
+                EgglogRules::default().try_add_rule_str("(ruleset div-ext) (include \"lib.egg\")"),
+                Err(EgglogProgramError::UnsupportedCommand {
+                    section: EgglogSection::Rules,
+                    ..
+                })
+            ),
+            "Commands no section holds should be unsupported."
+        );
+        assert_eq!(
+            1,
+            EgglogRules::default().add_rule_str(rule_str).len(),
+            "The infallible API should keep filtering other sections."
+        );
+    }
 }
@@ -0,0 +1,176 @@
+use std::time::{Duration, Instant};
+
+use derive_getters::Getters;
+use egglog::ast::{Command, GenericRunConfig, GenericSchedule, Schedule, Symbol};
+use egglog::EGraph;
+use indexmap::IndexMap;
+
+use crate::{EgglogCommandList, EgglogProgram, EgglogProgramError};
+
+#[derive(Debug, Clone, Default, Getters)]
+pub struct RunReport {
+    ruleset_iterations: IndexMap<Symbol, usize>,
+    ruleset_time: IndexMap<Symbol, Duration>,
+    rule_matches: IndexMap<Symbol, usize>,
+    search_time: Duration,
+    apply_time: Duration,
+    rebuild_time: Duration,
+    total_time: Duration,
+    num_tuples: usize,
+    outputs: Vec<String>,
+}
+
+impl RunReport {
+    fn record_iteration(&mut self, ruleset: Symbol, egglog_report: &egglog::RunReport) {
+        *self.ruleset_iterations.entry(ruleset).or_default() += 1;
+        for (rule, matches) in egglog_report.num_matches_per_rule.iter() {
+            *self.rule_matches.entry(*rule).or_default() += *matches;
+        }
+        let search_time: Duration = egglog_report.search_time_per_ruleset.values().sum();
+        let apply_time: Duration = egglog_report.apply_time_per_ruleset.values().sum();
+        let rebuild_time: Duration = egglog_report.rebuild_time_per_ruleset.values().sum();
+        *self.ruleset_time.entry(ruleset).or_default() += search_time + apply_time + rebuild_time;
+        self.search_time += search_time;
+        self.apply_time += apply_time;
+        self.rebuild_time += rebuild_time;
+    }
+
+    fn run_schedule(
+        &mut self,
+        egraph: &mut EGraph,
+        schedule: &Schedule,
+    ) -> Result<bool, EgglogProgramError> {
+        match schedule {
+            GenericSchedule::Saturate(_span, schedule) => {
+                let mut updated = false;
+                while self.run_schedule(egraph, schedule)? {
+                    updated = true;
+                }
+                Ok(updated)
+            }
+            GenericSchedule::Repeat(_span, limit, schedule) => {
+                let mut updated = false;
+                for _iteration in 0..*limit {
+                    if !self.run_schedule(egraph, schedule)? {
+                        break;
+                    }
+                    updated = true;
+                }
+                Ok(updated)
+            }
+            GenericSchedule::Sequence(_span, schedules) => {
+                let mut updated = false;
+                for schedule in schedules {
+                    updated |= self.run_schedule(egraph, schedule)?;
+                }
+                Ok(updated)
+            }
+            GenericSchedule::Run(span, run_config) => {
+                if let Some(until_facts) = &run_config.until {
+                    let until_check = Command::Check(span.clone(), until_facts.clone());
+                    // Only a failed check means the condition does not hold yet;
+                    // other errors, such as unknown functions, are real failures.
+                    match egraph.run_program(vec![until_check]) {
+                        Ok(_outputs) => return Ok(false),
+                        Err(egglog::Error::CheckError(..)) => {}
+                        Err(error) => return Err(error.into()),
+                    }
+                }
+                let single_run = Command::RunSchedule(GenericSchedule::Run(
+                    span.clone(),
+                    GenericRunConfig {
+                        ruleset: run_config.ruleset,
+                        until: None,
+                    },
+                ));
+                egraph.run_program(vec![single_run])?;
+                match egraph.get_run_report() {
+                    Some(egglog_report) => {
+                        self.record_iteration(run_config.ruleset, egglog_report);
+                        Ok(egglog_report.updated)
+                    }
+                    None => Ok(false),
+                }
+            }
+        }
+    }
+}
+
+impl EgglogProgram {
+    pub fn run(&self, egraph: &mut EGraph) -> Result<RunReport, EgglogProgramError> {
+        let run_start = Instant::now();
+        let mut run_report = RunReport::default();
+        for command in EgglogCommandList::from(self.clone()) {
+            match command {
+                Command::RunSchedule(schedule) => {
+                    run_report.run_schedule(egraph, &schedule)?;
+                }
+                command => {
+                    let mut outputs = egraph.run_program(vec![command])?;
+                    run_report.outputs.append(&mut outputs);
+                }
+            }
+        }
+        run_report.num_tuples = egraph.num_tuples();
+        run_report.total_time = run_start.elapsed();
+        Ok(run_report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn run_egglog_program_with_report() {
+        let program_str = indoc! {"
+            (datatype Math (Num i64) (Add Math Math))
+            (let expr1 (Add (Num 1) (Num 2)))
+            (ruleset math-rules)
+            (rewrite (Add a b) (Add b a) :ruleset math-rules)
+            (run-schedule (saturate (run math-rules)))
+            (check (= expr1 (Add (Num 2) (Num 1))))
+            (extract expr1)
+        "};
+        let (egglog_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(program_str).unwrap();
+        let mut egraph = EGraph::default();
+        let run_report = egglog_program.run(&mut egraph).unwrap();
+        let math_rules_iterations = run_report
+            .ruleset_iterations()
+            .get(&Symbol::new("math-rules"))
+            .copied()
+            .unwrap_or_default();
+        assert!(
+            math_rules_iterations >= 2,
+            "Saturation needs at least one updating and one idle iteration."
+        );
+        assert!(run_report.rule_matches().values().sum::<usize>() > 0);
+        assert!(*run_report.num_tuples() > 0);
+        assert!(
+            !run_report.outputs().is_empty(),
+            "The extract query should produce an output."
+        );
+    }
+
+    #[test]
+    fn run_until_reports_invalid_conditions() {
+        let program_str = indoc! {"
+            (datatype Math (Num i64) (Add Math Math))
+            (let expr1 (Add (Num 1) (Num 2)))
+            (ruleset math-rules)
+            (rewrite (Add a b) (Add b a) :ruleset math-rules)
+            (run-schedule (repeat 10 (run math-rules :until (= expr1 (Sub (Num 2) (Num 1))))))
+        "};
+        let (egglog_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(program_str).unwrap();
+        let mut egraph = EGraph::default();
+        assert!(
+            matches!(
+                egglog_program.run(&mut egraph),
+                Err(EgglogProgramError::Egglog { .. })
+            ),
+            "An until condition using an unknown function should fail the run."
+        );
+    }
+}
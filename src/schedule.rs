@@ -1,9 +1,48 @@
 use std::ops::Deref;
+use std::path::Path;
 
-use egglog::ast::Command;
+use egglog::ast::{Command, GenericSchedule, RunConfig, Schedule, Span, Symbol};
 use itertools::Itertools;
 
-use crate::EgglogCommandList;
+use crate::parse::{parse_egglog_file, parse_egglog_str};
+use crate::program::try_section_commands;
+use crate::{Diagnostics, EgglogCommandList, EgglogProgramError, EgglogSection};
+
+pub(crate) fn is_schedule_command(command: &Command) -> bool {
+    matches!(*command, Command::RunSchedule(..))
+}
+
+pub(crate) fn visit_run_configs<Visitor>(schedule: &Schedule, visitor: &mut Visitor)
+where
+    Visitor: FnMut(&Span, &RunConfig),
+{
+    match schedule {
+        GenericSchedule::Saturate(_span, schedule)
+        | GenericSchedule::Repeat(_span, _, schedule) => visit_run_configs(schedule, visitor),
+        GenericSchedule::Run(span, run_config) => visitor(span, run_config),
+        GenericSchedule::Sequence(_span, schedules) => schedules
+            .iter()
+            .for_each(|schedule| visit_run_configs(schedule, visitor)),
+    }
+}
+
+pub(crate) fn without_ruleset(schedule: &Schedule, ruleset: Symbol) -> Option<Schedule> {
+    match schedule {
+        GenericSchedule::Saturate(span, schedule) => without_ruleset(schedule, ruleset)
+            .map(|schedule| GenericSchedule::Saturate(span.clone(), Box::new(schedule))),
+        GenericSchedule::Repeat(span, times, schedule) => without_ruleset(schedule, ruleset)
+            .map(|schedule| GenericSchedule::Repeat(span.clone(), *times, Box::new(schedule))),
+        GenericSchedule::Run(_span, run_config) if run_config.ruleset == ruleset => None,
+        GenericSchedule::Run(..) => Some(schedule.clone()),
+        GenericSchedule::Sequence(span, schedules) => {
+            let schedules = schedules
+                .iter()
+                .filter_map(|schedule| without_ruleset(schedule, ruleset))
+                .collect_vec();
+            (!schedules.is_empty()).then(|| GenericSchedule::Sequence(span.clone(), schedules))
+        }
+    }
+}
 
 #[derive(Debug, Clone, Default)]
 pub struct EgglogSchedules(EgglogCommandList);
@@ -15,20 +54,70 @@ impl EgglogSchedules {
     {
         let mut schedules = schedule_list
             .into_iter()
-            .filter(|command| matches!(*command, Command::RunSchedule(..)))
+            .filter(is_schedule_command)
             .collect_vec();
         let mut updated_schedules = Self(self.0);
         updated_schedules.0.append(&mut schedules);
         updated_schedules
     }
 
+    pub fn add_schedule_checked<CommandList>(
+        self,
+        schedule_list: CommandList,
+    ) -> (Self, Diagnostics)
+    where
+        CommandList: IntoIterator<Item = Command>,
+    {
+        let (accepted, rejected): (EgglogCommandList, EgglogCommandList) =
+            schedule_list.into_iter().partition(is_schedule_command);
+        (
+            Self::add_schedule(self, accepted),
+            Diagnostics::from_rejected(EgglogSection::Schedules, rejected),
+        )
+    }
+
+    pub fn try_add_schedule_str(self, schedule_str: &str) -> Result<Self, EgglogProgramError> {
+        let schedule_commands = try_section_commands(
+            EgglogSection::Schedules,
+            parse_egglog_str(None, schedule_str)?,
+            is_schedule_command,
+        )?;
+        Ok(Self::add_schedule(self, schedule_commands))
+    }
+
     pub fn add_schedule_str(self, schedule_str: &str) -> Self {
-        let mut egglog_parser = egglog::ast::Parser::default();
-        match egglog_parser.get_program_from_string(None, schedule_str) {
+        match parse_egglog_str(None, schedule_str) {
             Ok(schedule_commands) => Self::add_schedule(self, schedule_commands),
             Err(error) => panic!("Failure to build schedule from string: {:?}", error),
         }
     }
+
+    pub fn add_schedule_str_checked(
+        self,
+        schedule_str: &str,
+    ) -> Result<(Self, Diagnostics), EgglogProgramError> {
+        let schedule_commands = parse_egglog_str(None, schedule_str)?;
+        Ok(Self::add_schedule_checked(self, schedule_commands))
+    }
+
+    pub fn try_add_schedule_file<P: AsRef<Path>>(
+        self,
+        schedule_path: P,
+    ) -> Result<Self, EgglogProgramError> {
+        let schedule_commands = try_section_commands(
+            EgglogSection::Schedules,
+            parse_egglog_file(schedule_path)?,
+            is_schedule_command,
+        )?;
+        Ok(Self::add_schedule(self, schedule_commands))
+    }
+
+    pub fn add_schedule_file<P: AsRef<Path>>(self, schedule_path: P) -> Self {
+        match parse_egglog_file(schedule_path) {
+            Ok(schedule_commands) => Self::add_schedule(self, schedule_commands),
+            Err(error) => panic!("Failure to build schedule from file: {:?}", error),
+        }
+    }
 }
 
 impl Deref for EgglogSchedules {
@@ -87,4 +176,14 @@ mod tests {
             "There should be 1 commands present, one for each schedule."
         );
     }
+
+    #[test]
+    fn try_create_egglog_schedule_from_malformed_str() {
+        let schedule_result = EgglogSchedules::default()
+            .try_add_schedule_str("(run-schedule (saturate (run div-ext))");
+        assert!(
+            matches!(schedule_result, Err(EgglogProgramError::Parse { .. })),
+            "Unbalanced parentheses should be reported as a parse error."
+        );
+    }
 }
@@ -0,0 +1,133 @@
+use egglog::ast::{Command, Fact, GenericRunConfig, GenericSchedule, Schedule, Symbol};
+
+use crate::rules::declared_rulesets;
+use crate::schedule::visit_run_configs;
+use crate::{EgglogProgramError, EgglogRules, EgglogSchedules, ValidationIssue, DUMMY_SPAN};
+
+#[derive(Debug, Clone)]
+pub struct ScheduleBuilder(Schedule);
+
+impl ScheduleBuilder {
+    pub fn run<Ruleset: Into<Symbol>>(ruleset: Ruleset) -> Self {
+        Self(GenericSchedule::Run(
+            DUMMY_SPAN.clone(),
+            GenericRunConfig {
+                ruleset: ruleset.into(),
+                until: None,
+            },
+        ))
+    }
+
+    pub fn run_until<Ruleset, FactList>(ruleset: Ruleset, until: FactList) -> Self
+    where
+        Ruleset: Into<Symbol>,
+        FactList: IntoIterator<Item = Fact>,
+    {
+        Self(GenericSchedule::Run(
+            DUMMY_SPAN.clone(),
+            GenericRunConfig {
+                ruleset: ruleset.into(),
+                until: Some(until.into_iter().collect()),
+            },
+        ))
+    }
+
+    pub fn repeat(times: usize, schedule: Self) -> Self {
+        Self(GenericSchedule::Repeat(
+            DUMMY_SPAN.clone(),
+            times,
+            Box::new(schedule.0),
+        ))
+    }
+
+    pub fn saturate(schedule: Self) -> Self {
+        Self(GenericSchedule::Saturate(
+            DUMMY_SPAN.clone(),
+            Box::new(schedule.0),
+        ))
+    }
+
+    pub fn seq<ScheduleList>(schedules: ScheduleList) -> Self
+    where
+        ScheduleList: IntoIterator<Item = Self>,
+    {
+        Self(GenericSchedule::Sequence(
+            DUMMY_SPAN.clone(),
+            schedules.into_iter().map(|schedule| schedule.0).collect(),
+        ))
+    }
+
+    pub fn build(self) -> Schedule {
+        self.0
+    }
+
+    pub fn schedules(self) -> EgglogSchedules {
+        EgglogSchedules::default().add_schedule([Command::RunSchedule(self.0)])
+    }
+
+    pub fn try_schedules(self, rules: &EgglogRules) -> Result<EgglogSchedules, EgglogProgramError> {
+        let rulesets = declared_rulesets(rules.iter());
+        let mut issues = vec![];
+        visit_run_configs(&self.0, &mut |span, run_config| {
+            if !rulesets.contains(&run_config.ruleset) {
+                issues.push(ValidationIssue::UnknownRuleset {
+                    ruleset: run_config.ruleset,
+                    span: span.clone(),
+                });
+            }
+        });
+        if issues.is_empty() {
+            Ok(self.schedules())
+        } else {
+            Err(EgglogProgramError::Validation(issues))
+        }
+    }
+}
+
+impl From<ScheduleBuilder> for Schedule {
+    fn from(schedule_builder: ScheduleBuilder) -> Self {
+        schedule_builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egglog::ast::GenericFact;
+    use indoc::indoc;
+
+    use super::*;
+    use crate::rule_builder::{call, var};
+
+    #[test]
+    fn build_schedule_from_combinators() {
+        let rules = EgglogRules::default().add_rule_str(indoc! {"
+            (ruleset comm-rules)
+            (ruleset zero-rules)
+        "});
+        let schedule = ScheduleBuilder::seq([
+            ScheduleBuilder::saturate(ScheduleBuilder::run("zero-rules")),
+            ScheduleBuilder::repeat(3, ScheduleBuilder::run("comm-rules")),
+        ]);
+        assert_eq!(
+            "(seq (saturate (run zero-rules)) (repeat 3 (run comm-rules)))",
+            schedule.clone().build().to_string(),
+            "Combinators should nest into a single schedule."
+        );
+        assert_eq!(1, schedule.try_schedules(&rules).unwrap().len());
+        let until_schedule = ScheduleBuilder::run_until(
+            "comm-rules",
+            [GenericFact::Fact(call("Add", [var("x"), var("y")]))],
+        )
+        .build();
+        assert!(matches!(
+            until_schedule,
+            GenericSchedule::Run(_span, GenericRunConfig { until: Some(facts), .. }) if facts.len() == 1
+        ));
+        let unknown_ruleset = ScheduleBuilder::run("math-rules").try_schedules(&rules);
+        assert!(matches!(
+            unknown_ruleset,
+            Err(EgglogProgramError::Validation(issues))
+                if matches!(issues[0], ValidationIssue::UnknownRuleset { ruleset, .. } if ruleset == Symbol::new("math-rules"))
+        ));
+    }
+}
@@ -1,13 +1,28 @@
 use std::ops::Deref;
+use std::path::Path;
 
-use egglog::ast::{Command, Symbol};
+use egglog::ast::{Command, Expr, GenericExpr, Subdatatypes, Symbol};
+use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools;
 
-use crate::EgglogCommandList;
+use crate::parse::{parse_egglog_file, parse_egglog_str};
+use crate::program::try_section_commands;
+use crate::{
+    Diagnostics, EgglogCommandList, EgglogProgramError, EgglogSection, EgglogSymbols, DUMMY_SPAN,
+};
 
-pub(crate) fn get_sort_symbol(command: &Command) -> Vec<Symbol> {
+pub(crate) fn is_sort_command(command: &Command) -> bool {
+    matches!(*command, Command::Sort(..))
+        || matches!(*command, Command::Datatype { .. })
+        || matches!(*command, Command::Datatypes { .. })
+        || matches!(*command, Command::Relation { .. })
+        || matches!(*command, Command::Function { .. })
+        || matches!(*command, Command::Constructor { .. })
+}
+
+pub(crate) fn try_get_sort_symbol(command: &Command) -> Result<Vec<Symbol>, EgglogProgramError> {
     match command {
-        Command::Sort(_span, symbol, _expr) => vec![symbol.to_owned()],
+        Command::Sort(_span, symbol, _expr) => Ok(vec![symbol.to_owned()]),
         Command::Datatype {
             span: _span,
             name: symbol,
@@ -15,19 +30,169 @@ pub(crate) fn get_sort_symbol(command: &Command) -> Vec<Symbol> {
         } => {
             let mut variant_symbols = variants.iter().map(|variant| variant.name).collect_vec();
             variant_symbols.insert(0, symbol.to_owned());
-            variant_symbols
+            Ok(variant_symbols)
         }
+        Command::Datatypes {
+            span: _span,
+            datatypes,
+        } => Ok(datatypes
+            .iter()
+            .flat_map(|(_span, symbol, subdatatypes)| {
+                let mut datatype_symbols = vec![*symbol];
+                if let Subdatatypes::Variants(variants) = subdatatypes {
+                    datatype_symbols.extend(variants.iter().map(|variant| variant.name));
+                }
+                datatype_symbols
+            })
+            .collect_vec()),
         Command::Relation {
             span: _span,
             name: symbol,
             inputs: _inputs,
-        } => vec![symbol.to_owned()],
-        Command::Function { name, .. } => vec![*name],
-        Command::Constructor { name, .. } => vec![*name],
-        _ => panic!("Egglog Command not supported in EgglogSorts {:?}.", command),
+        } => Ok(vec![symbol.to_owned()]),
+        Command::Function { name, .. } => Ok(vec![*name]),
+        Command::Constructor { name, .. } => Ok(vec![*name]),
+        _ => Err(EgglogProgramError::SectionMismatch {
+            section: EgglogSection::Sorts,
+            command: command.clone(),
+        }),
     }
 }
 
+pub(crate) fn get_sort_symbol(command: &Command) -> Vec<Symbol> {
+    match try_get_sort_symbol(command) {
+        Ok(sort_symbols) => sort_symbols,
+        Err(_) => panic!("Egglog Command not supported in EgglogSorts {:?}.", command),
+    }
+}
+
+fn expr_symbols(expr: &Expr, symbols: &mut EgglogSymbols) {
+    match expr {
+        GenericExpr::Lit(..) => {}
+        GenericExpr::Var(_span, symbol) => {
+            symbols.insert(*symbol);
+        }
+        GenericExpr::Call(_span, head, args) => {
+            symbols.insert(*head);
+            args.iter().for_each(|arg| expr_symbols(arg, symbols));
+        }
+    }
+}
+
+fn defined_sorts(command: &Command) -> Vec<Symbol> {
+    match command {
+        Command::Sort(_span, symbol, _presort) => vec![*symbol],
+        Command::Datatype { name, .. } => vec![*name],
+        Command::Datatypes { datatypes, .. } => datatypes
+            .iter()
+            .map(|(_span, symbol, _subdatatypes)| *symbol)
+            .collect_vec(),
+        _ => vec![],
+    }
+}
+
+fn sort_dependencies(command: &Command) -> EgglogSymbols {
+    let mut dependencies = EgglogSymbols::default();
+    match command {
+        Command::Sort(_span, _symbol, Some((_presort, args))) => {
+            args.iter()
+                .for_each(|arg| expr_symbols(arg, &mut dependencies));
+        }
+        Command::Datatype { variants, .. } => {
+            dependencies.extend(variants.iter().flat_map(|variant| variant.types.iter()));
+        }
+        Command::Datatypes { datatypes, .. } => {
+            for (_span, _symbol, subdatatypes) in datatypes {
+                match subdatatypes {
+                    Subdatatypes::Variants(variants) => dependencies
+                        .extend(variants.iter().flat_map(|variant| variant.types.iter())),
+                    Subdatatypes::NewSort(_presort, args) => args
+                        .iter()
+                        .for_each(|arg| expr_symbols(arg, &mut dependencies)),
+                }
+            }
+        }
+        Command::Relation { inputs, .. } => dependencies.extend(inputs.iter()),
+        Command::Function { schema, .. } | Command::Constructor { schema, .. } => {
+            dependencies.extend(schema.input.iter());
+            dependencies.insert(schema.output);
+        }
+        _ => {}
+    }
+    dependencies
+}
+
+struct SortComponents<'a> {
+    dependencies: &'a [IndexSet<usize>],
+    indices: Vec<Option<usize>>,
+    low_links: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    next_index: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl SortComponents<'_> {
+    fn connect(&mut self, node: usize) {
+        self.indices[node] = Some(self.next_index);
+        self.low_links[node] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+        for dependency in self.dependencies[node].iter().copied() {
+            match self.indices[dependency] {
+                None => {
+                    self.connect(dependency);
+                    self.low_links[node] = self.low_links[node].min(self.low_links[dependency]);
+                }
+                Some(index) if self.on_stack[dependency] => {
+                    self.low_links[node] = self.low_links[node].min(index);
+                }
+                Some(_) => {}
+            }
+        }
+        if Some(self.low_links[node]) == self.indices[node] {
+            let mut component = vec![];
+            while let Some(member) = self.stack.pop() {
+                self.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            component.sort_unstable();
+            self.components.push(component);
+        }
+    }
+}
+
+fn group_datatypes(commands: &[&Command]) -> Option<Command> {
+    let datatypes = commands
+        .iter()
+        .map(|command| match command {
+            Command::Datatype {
+                span,
+                name,
+                variants,
+            } => Some((
+                span.clone(),
+                *name,
+                Subdatatypes::Variants(variants.clone()),
+            )),
+            Command::Sort(span, name, Some((presort, args))) => Some((
+                span.clone(),
+                *name,
+                Subdatatypes::NewSort(*presort, args.clone()),
+            )),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(Command::Datatypes {
+        span: DUMMY_SPAN.clone(),
+        datatypes,
+    })
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct EgglogSorts(EgglogCommandList);
 
@@ -36,28 +201,126 @@ impl EgglogSorts {
     where
         CommandList: IntoIterator<Item = Command>,
     {
-        let mut sorts = sort_list
-            .into_iter()
-            .filter(|command| {
-                matches!(*command, Command::Sort(..))
-                    || matches!(*command, Command::Datatype { .. })
-                    || matches!(*command, Command::Relation { .. })
-                    || matches!(*command, Command::Function { .. })
-                    || matches!(*command, Command::Constructor { .. })
-            })
-            .collect_vec();
+        let mut sorts = sort_list.into_iter().filter(is_sort_command).collect_vec();
         let mut updated_sorts = Self(self.0);
         updated_sorts.0.append(&mut sorts);
         updated_sorts
     }
 
+    pub fn dependency_ordered(&self) -> Self {
+        let mut definitions: IndexMap<Symbol, usize> = IndexMap::new();
+        for (index, command) in self.0.iter().enumerate() {
+            for symbol in defined_sorts(command) {
+                definitions.entry(symbol).or_insert(index);
+            }
+        }
+        let dependencies = self
+            .0
+            .iter()
+            .enumerate()
+            .map(|(index, command)| {
+                sort_dependencies(command)
+                    .iter()
+                    .filter_map(|symbol| definitions.get(symbol).copied())
+                    .filter(|dependency| *dependency != index)
+                    .collect::<IndexSet<usize>>()
+            })
+            .collect_vec();
+
+        let mut sort_components = SortComponents {
+            dependencies: &dependencies,
+            indices: vec![None; self.0.len()],
+            low_links: vec![0; self.0.len()],
+            stack: vec![],
+            on_stack: vec![false; self.0.len()],
+            next_index: 0,
+            components: vec![],
+        };
+        for node in 0..self.0.len() {
+            if sort_components.indices[node].is_none() {
+                sort_components.connect(node);
+            }
+        }
+        let mut components = sort_components.components;
+        components.sort_by_key(|component| component[0]);
+
+        let mut emitted = vec![false; self.0.len()];
+        let mut ordered_sorts = EgglogCommandList::default();
+        while let Some(position) = components.iter().position(|component| {
+            component.iter().all(|member| {
+                dependencies[*member]
+                    .iter()
+                    .all(|dependency| emitted[*dependency] || component.contains(dependency))
+            })
+        }) {
+            let component = components.remove(position);
+            let commands = component
+                .iter()
+                .map(|member| &self.0[*member])
+                .collect_vec();
+            match group_datatypes(&commands) {
+                Some(datatypes) if commands.len() > 1 => ordered_sorts.push(datatypes),
+                _ => ordered_sorts.extend(commands.into_iter().cloned()),
+            }
+            component.iter().for_each(|member| emitted[*member] = true);
+        }
+        Self(ordered_sorts)
+    }
+
+    pub fn add_sorts_checked<CommandList>(self, sort_list: CommandList) -> (Self, Diagnostics)
+    where
+        CommandList: IntoIterator<Item = Command>,
+    {
+        let (accepted, rejected): (EgglogCommandList, EgglogCommandList) =
+            sort_list.into_iter().partition(is_sort_command);
+        (
+            Self::add_sorts(self, accepted),
+            Diagnostics::from_rejected(EgglogSection::Sorts, rejected),
+        )
+    }
+
+    pub fn try_add_sort_str(self, sort_str: &str) -> Result<Self, EgglogProgramError> {
+        let sort_commands = try_section_commands(
+            EgglogSection::Sorts,
+            parse_egglog_str(None, sort_str)?,
+            is_sort_command,
+        )?;
+        Ok(Self::add_sorts(self, sort_commands))
+    }
+
     pub fn add_sort_str(self, sort_str: &str) -> Self {
-        let mut egglog_parser = egglog::ast::Parser::default();
-        match egglog_parser.get_program_from_string(None, sort_str) {
+        match parse_egglog_str(None, sort_str) {
             Ok(sort_commands) => Self::add_sorts(self, sort_commands),
             Err(error) => panic!("Failure to build sorts from string: {:?}", error),
         }
     }
+
+    pub fn add_sort_str_checked(
+        self,
+        sort_str: &str,
+    ) -> Result<(Self, Diagnostics), EgglogProgramError> {
+        let sort_commands = parse_egglog_str(None, sort_str)?;
+        Ok(Self::add_sorts_checked(self, sort_commands))
+    }
+
+    pub fn try_add_sort_file<P: AsRef<Path>>(
+        self,
+        sort_path: P,
+    ) -> Result<Self, EgglogProgramError> {
+        let sort_commands = try_section_commands(
+            EgglogSection::Sorts,
+            parse_egglog_file(sort_path)?,
+            is_sort_command,
+        )?;
+        Ok(Self::add_sorts(self, sort_commands))
+    }
+
+    pub fn add_sort_file<P: AsRef<Path>>(self, sort_path: P) -> Self {
+        match parse_egglog_file(sort_path) {
+            Ok(sort_commands) => Self::add_sorts(self, sort_commands),
+            Err(error) => panic!("Failure to build sorts from file: {:?}", error),
+        }
+    }
 }
 
 impl Deref for EgglogSorts {
@@ -95,6 +358,8 @@ impl IntoIterator for EgglogSorts {
 
 #[cfg(test)]
 mod tests {
+    use indoc::indoc;
+
     use super::*;
     use crate::DUMMY_SPAN;
     use egglog::ast::{GenericCommand, Schema, Symbol};
@@ -140,4 +405,44 @@ mod tests {
             "There should be 4 sorts/datatypes/declarations present in program."
         );
     }
+
+    #[test]
+    fn order_sorts_by_dependency() {
+        let egglog_sorts = EgglogSorts::default().add_sort_str(indoc! {"
+            (sort LLHDVecTy (Vec LLHDTy))
+            (datatype LLHDTy (IntTy i64) (StructTy LLHDTyList))
+            (datatype LLHDTyList (Nil) (Cons LLHDTy LLHDTyList))
+            (relation ty-width (LLHDTy i64))
+            (datatype Math (Num i64))
+        "});
+        let ordered_sorts = egglog_sorts.dependency_ordered();
+        assert_eq!(4, ordered_sorts.len());
+        assert!(
+            matches!(ordered_sorts[0], Command::Datatypes { .. }),
+            "Mutually recursive datatypes should be grouped into datatype*."
+        );
+        assert!(matches!(ordered_sorts[1], Command::Sort(..)));
+        assert!(matches!(ordered_sorts[2], Command::Relation { .. }));
+        assert!(matches!(ordered_sorts[3], Command::Datatype { .. }));
+        assert_eq!(
+            vec![
+                Symbol::new("LLHDTy"),
+                Symbol::new("IntTy"),
+                Symbol::new("StructTy"),
+                Symbol::new("LLHDTyList"),
+                Symbol::new("Nil"),
+                Symbol::new("Cons"),
+            ],
+            get_sort_symbol(&ordered_sorts[0])
+        );
+    }
+
+    #[test]
+    fn try_create_egglog_sorts_from_malformed_str() {
+        let sort_result = EgglogSorts::default().try_add_sort_str("(datatype Math (Num i64)");
+        assert!(
+            matches!(sort_result, Err(EgglogProgramError::Parse { .. })),
+            "Unbalanced parentheses should be reported as a parse error."
+        );
+    }
 }
@@ -0,0 +1,158 @@
+use std::rc::Rc;
+use std::sync::Arc;
+
+use egglog::ast::{Literal, Symbol};
+use egglog::{Term, TermDag};
+
+use crate::egraph::egglog_names::{
+    EGGLOG_BOOL_SORT, EGGLOG_F64_SORT, EGGLOG_I64_SORT, EGGLOG_STRING_SORT, EGGLOG_UNIT_SORT,
+    EGGLOG_VEC_OF_OP, EGGLOG_VEC_SORT,
+};
+use crate::{ExtractionResult, TermDecodeError};
+
+pub trait FromEgglogTerm: Sized {
+    fn from_egglog_term(termdag: &TermDag, term: &Term) -> Result<Self, TermDecodeError>;
+}
+
+fn unexpected_term(sort: &str, termdag: &TermDag, term: &Term) -> TermDecodeError {
+    TermDecodeError::UnexpectedTerm {
+        sort: Symbol::from(sort),
+        term: termdag.to_string(term),
+    }
+}
+
+fn integer_value<Value: TryFrom<i64>>(
+    termdag: &TermDag,
+    term: &Term,
+) -> Result<Value, TermDecodeError> {
+    match term {
+        Term::Lit(Literal::Int(value)) => {
+            Value::try_from(*value).map_err(|_| unexpected_term(EGGLOG_I64_SORT, termdag, term))
+        }
+        _ => Err(unexpected_term(EGGLOG_I64_SORT, termdag, term)),
+    }
+}
+
+macro_rules! integer_from_egglog_term {
+    ($($integer:ty),+) => {
+        $(
+            impl FromEgglogTerm for $integer {
+                fn from_egglog_term(termdag: &TermDag, term: &Term) -> Result<Self, TermDecodeError> {
+                    integer_value(termdag, term)
+                }
+            }
+        )+
+    };
+}
+
+integer_from_egglog_term!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl FromEgglogTerm for f64 {
+    fn from_egglog_term(termdag: &TermDag, term: &Term) -> Result<Self, TermDecodeError> {
+        match term {
+            Term::Lit(Literal::Float(value)) => Ok(value.0),
+            _ => Err(unexpected_term(EGGLOG_F64_SORT, termdag, term)),
+        }
+    }
+}
+
+impl FromEgglogTerm for f32 {
+    fn from_egglog_term(termdag: &TermDag, term: &Term) -> Result<Self, TermDecodeError> {
+        f64::from_egglog_term(termdag, term).map(|value| value as f32)
+    }
+}
+
+impl FromEgglogTerm for () {
+    fn from_egglog_term(termdag: &TermDag, term: &Term) -> Result<Self, TermDecodeError> {
+        match term {
+            Term::Lit(Literal::Unit) => Ok(()),
+            _ => Err(unexpected_term(EGGLOG_UNIT_SORT, termdag, term)),
+        }
+    }
+}
+
+impl FromEgglogTerm for bool {
+    fn from_egglog_term(termdag: &TermDag, term: &Term) -> Result<Self, TermDecodeError> {
+        match term {
+            Term::Lit(Literal::Bool(value)) => Ok(*value),
+            _ => Err(unexpected_term(EGGLOG_BOOL_SORT, termdag, term)),
+        }
+    }
+}
+
+impl FromEgglogTerm for String {
+    fn from_egglog_term(termdag: &TermDag, term: &Term) -> Result<Self, TermDecodeError> {
+        match term {
+            Term::Lit(Literal::String(value)) => Ok(value.to_string()),
+            _ => Err(unexpected_term(EGGLOG_STRING_SORT, termdag, term)),
+        }
+    }
+}
+
+impl<Element: FromEgglogTerm> FromEgglogTerm for Vec<Element> {
+    fn from_egglog_term(termdag: &TermDag, term: &Term) -> Result<Self, TermDecodeError> {
+        match term {
+            Term::App(op, children) if *op == Symbol::from(EGGLOG_VEC_OF_OP) => children
+                .iter()
+                .map(|child| Element::from_egglog_term(termdag, &termdag.get(*child)))
+                .collect(),
+            _ => Err(unexpected_term(EGGLOG_VEC_SORT, termdag, term)),
+        }
+    }
+}
+
+impl<Value: FromEgglogTerm> FromEgglogTerm for Box<Value> {
+    fn from_egglog_term(termdag: &TermDag, term: &Term) -> Result<Self, TermDecodeError> {
+        Value::from_egglog_term(termdag, term).map(Box::new)
+    }
+}
+
+impl<Value: FromEgglogTerm> FromEgglogTerm for Rc<Value> {
+    fn from_egglog_term(termdag: &TermDag, term: &Term) -> Result<Self, TermDecodeError> {
+        Value::from_egglog_term(termdag, term).map(Rc::new)
+    }
+}
+
+impl<Value: FromEgglogTerm> FromEgglogTerm for Arc<Value> {
+    fn from_egglog_term(termdag: &TermDag, term: &Term) -> Result<Self, TermDecodeError> {
+        Value::from_egglog_term(termdag, term).map(Arc::new)
+    }
+}
+
+impl ExtractionResult {
+    pub fn decode<Value: FromEgglogTerm>(&self) -> Result<Value, TermDecodeError> {
+        Value::from_egglog_term(self.termdag(), self.term())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_primitive_terms() {
+        let mut termdag = TermDag::default();
+        let one = termdag.lit(Literal::Int(1));
+        let two = termdag.lit(Literal::Int(2));
+        let vector = termdag.app(Symbol::from(EGGLOG_VEC_OF_OP), vec![one.clone(), two]);
+        assert_eq!(
+            Ok(vec![1i64, 2]),
+            Vec::<i64>::from_egglog_term(&termdag, &vector),
+            "vec-of terms should decode element-wise."
+        );
+        assert_eq!(
+            Err(TermDecodeError::UnexpectedTerm {
+                sort: Symbol::from(EGGLOG_STRING_SORT),
+                term: String::from("1"),
+            }),
+            String::from_egglog_term(&termdag, &one),
+            "Mismatched literals should be reported with the expected sort."
+        );
+        let negative = termdag.lit(Literal::Int(-1));
+        assert_eq!(Ok(1u8), u8::from_egglog_term(&termdag, &one));
+        assert!(
+            u64::from_egglog_term(&termdag, &negative).is_err(),
+            "Out of range integers should not decode."
+        );
+    }
+}
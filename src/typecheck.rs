@@ -0,0 +1,315 @@
+use egglog::ast::{
+    Action, Command, Expr, Fact, GenericAction, GenericExpr, GenericFact, Literal, Span,
+    Subdatatypes, Symbol,
+};
+use indexmap::IndexMap;
+use itertools::Itertools;
+
+use crate::egraph::egglog_names::{
+    EGGLOG_BOOL_SORT, EGGLOG_F64_SORT, EGGLOG_I64_SORT, EGGLOG_STRING_SORT, EGGLOG_UNIT_SORT,
+};
+use crate::validate::fact_exprs;
+use crate::{EgglogProgram, EgglogSorts, ValidationIssue};
+
+pub(crate) type SortEnvironment = IndexMap<Symbol, Symbol>;
+
+#[derive(Debug, Clone)]
+pub(crate) struct SortSignature {
+    pub(crate) inputs: Vec<Symbol>,
+    pub(crate) output: Symbol,
+}
+
+pub(crate) fn sort_signatures(sorts: &EgglogSorts) -> IndexMap<Symbol, SortSignature> {
+    let mut signatures = IndexMap::new();
+    for command in sorts.iter() {
+        match command {
+            Command::Datatype { name, variants, .. } => {
+                for variant in variants {
+                    signatures.insert(
+                        variant.name,
+                        SortSignature {
+                            inputs: variant.types.clone(),
+                            output: *name,
+                        },
+                    );
+                }
+            }
+            Command::Datatypes { datatypes, .. } => {
+                for (_span, name, subdatatypes) in datatypes {
+                    if let Subdatatypes::Variants(variants) = subdatatypes {
+                        for variant in variants {
+                            signatures.insert(
+                                variant.name,
+                                SortSignature {
+                                    inputs: variant.types.clone(),
+                                    output: *name,
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+            Command::Relation { name, inputs, .. } => {
+                signatures.insert(
+                    *name,
+                    SortSignature {
+                        inputs: inputs.clone(),
+                        output: Symbol::from(EGGLOG_UNIT_SORT),
+                    },
+                );
+            }
+            Command::Function { name, schema, .. } | Command::Constructor { name, schema, .. } => {
+                signatures.insert(
+                    *name,
+                    SortSignature {
+                        inputs: schema.input.clone(),
+                        output: schema.output,
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+    signatures
+}
+
+fn literal_sort(literal: &Literal) -> Symbol {
+    match literal {
+        Literal::Int(..) => Symbol::from(EGGLOG_I64_SORT),
+        Literal::Float(..) => Symbol::from(EGGLOG_F64_SORT),
+        Literal::String(..) => Symbol::from(EGGLOG_STRING_SORT),
+        Literal::Bool(..) => Symbol::from(EGGLOG_BOOL_SORT),
+        Literal::Unit => Symbol::from(EGGLOG_UNIT_SORT),
+    }
+}
+
+struct SortChecker {
+    signatures: IndexMap<Symbol, SortSignature>,
+    globals: SortEnvironment,
+    issues: Vec<ValidationIssue>,
+}
+
+impl SortChecker {
+    fn infer_expr(
+        &mut self,
+        expr: &Expr,
+        expected: Option<Symbol>,
+        env: &mut SortEnvironment,
+    ) -> Option<Symbol> {
+        match expr {
+            GenericExpr::Lit(_span, literal) => Some(literal_sort(literal)),
+            GenericExpr::Var(_span, var) => {
+                let sort = env.get(var).or(self.globals.get(var)).copied().or(expected);
+                if let Some(sort) = sort {
+                    env.entry(*var).or_insert(sort);
+                }
+                sort
+            }
+            GenericExpr::Call(span, head, args) => {
+                let Some(signature) = self.signatures.get(head).cloned() else {
+                    args.iter().for_each(|arg| {
+                        self.infer_expr(arg, None, env);
+                    });
+                    return None;
+                };
+                let actual = args
+                    .iter()
+                    .enumerate()
+                    .map(|(index, arg)| {
+                        let expected = signature.inputs.get(index).copied();
+                        self.infer_expr(arg, expected, env)
+                    })
+                    .collect_vec();
+                let mismatched = actual.len() != signature.inputs.len()
+                    || actual
+                        .iter()
+                        .zip(signature.inputs.iter())
+                        .any(|(actual, expected)| actual.is_some_and(|actual| actual != *expected));
+                if mismatched {
+                    self.issues.push(ValidationIssue::SortMismatch {
+                        head: *head,
+                        expected: signature.inputs.clone(),
+                        actual: actual
+                            .iter()
+                            .map(|actual| actual.unwrap_or_else(|| Symbol::from("_")))
+                            .collect(),
+                        span: span.clone(),
+                    });
+                }
+                Some(signature.output)
+            }
+        }
+    }
+
+    fn check_same_sort(
+        &mut self,
+        head: &str,
+        (lhs, rhs): (&Expr, &Expr),
+        span: &Span,
+        env: &mut SortEnvironment,
+    ) {
+        let lhs_sort = self.infer_expr(lhs, None, env);
+        let rhs_sort = self.infer_expr(rhs, lhs_sort, env);
+        if let (Some(lhs_sort), Some(rhs_sort)) = (lhs_sort, rhs_sort) {
+            if lhs_sort != rhs_sort {
+                self.issues.push(ValidationIssue::SortMismatch {
+                    head: Symbol::from(head),
+                    expected: vec![lhs_sort, lhs_sort],
+                    actual: vec![lhs_sort, rhs_sort],
+                    span: span.clone(),
+                });
+            }
+        }
+    }
+
+    fn check_fact(&mut self, fact: &Fact, env: &mut SortEnvironment) {
+        let exprs = fact_exprs(fact);
+        let mut fact_sort = None;
+        for expr in exprs.iter() {
+            fact_sort = fact_sort.or(self.infer_expr(expr, None, env));
+        }
+        if let Some(fact_sort) = fact_sort {
+            for expr in exprs {
+                if let GenericExpr::Var(_span, var) = expr {
+                    env.entry(*var).or_insert(fact_sort);
+                }
+            }
+        }
+    }
+
+    fn check_action(&mut self, action: &Action, env: &mut SortEnvironment) {
+        match action {
+            GenericAction::Let(_span, var, expr) => {
+                if let Some(sort) = self.infer_expr(expr, None, env) {
+                    env.insert(*var, sort);
+                }
+            }
+            GenericAction::Set(span, head, args, value) => {
+                let call = GenericExpr::Call(span.clone(), *head, args.clone());
+                let sort = self.infer_expr(&call, None, env);
+                self.infer_expr(value, sort, env);
+            }
+            GenericAction::Change(span, _change, head, args) => {
+                let call = GenericExpr::Call(span.clone(), *head, args.clone());
+                self.infer_expr(&call, None, env);
+            }
+            GenericAction::Union(span, lhs, rhs) => {
+                self.check_same_sort("union", (lhs, rhs), span, env);
+            }
+            GenericAction::Extract(_span, expr, variants) => {
+                self.infer_expr(expr, None, env);
+                self.infer_expr(variants, None, env);
+            }
+            GenericAction::Panic(..) => {}
+            GenericAction::Expr(_span, expr) => {
+                self.infer_expr(expr, None, env);
+            }
+        }
+    }
+
+    fn check_rule_command(&mut self, command: &Command) {
+        let mut env = SortEnvironment::default();
+        match command {
+            Command::Rule { rule, .. } => {
+                rule.body
+                    .iter()
+                    .for_each(|fact| self.check_fact(fact, &mut env));
+                rule.head
+                    .0
+                    .iter()
+                    .for_each(|action| self.check_action(action, &mut env));
+            }
+            Command::Rewrite(_ruleset, rewrite, _subsume)
+            | Command::BiRewrite(_ruleset, rewrite) => {
+                rewrite
+                    .conditions
+                    .iter()
+                    .for_each(|fact| self.check_fact(fact, &mut env));
+                self.check_same_sort(
+                    "rewrite",
+                    (&rewrite.lhs, &rewrite.rhs),
+                    &rewrite.span,
+                    &mut env,
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+impl EgglogProgram {
+    pub fn sort_issues(&self) -> Vec<ValidationIssue> {
+        let mut checker = SortChecker {
+            signatures: sort_signatures(&self.sorts().1),
+            globals: SortEnvironment::default(),
+            issues: vec![],
+        };
+        for command in self.facts().1.iter() {
+            if let Command::Action(action) = command {
+                let mut fact_env = SortEnvironment::default();
+                checker.check_action(action, &mut fact_env);
+                checker.globals.extend(fact_env);
+            }
+        }
+        for command in self.rules().iter().flat_map(|rules| rules.iter()) {
+            checker.check_rule_command(command);
+        }
+        checker.issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn report_sort_mismatch_in_facts_and_rules() {
+        let program_str = indoc! {"
+            (datatype Math (Num i64) (Add Math Math))
+            (let expr1 (Add (Num 1) (Num 2)))
+            (let expr2 (Add expr1 3))
+            (let expr3 (Num expr1 expr1))
+            (ruleset math-rules)
+            (rewrite (Add a b) (Add b a) :ruleset math-rules)
+            (rule ((= e (Add x y))) ((union e (Num x))) :ruleset math-rules)
+        "};
+        let (egglog_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(program_str).unwrap();
+        let issues = egglog_program.sort_issues();
+        assert_eq!(3, issues.len(), "Every ill-sorted call should be reported.");
+        assert!(issues[0]
+            .to_string()
+            .starts_with("Add expects (Math Math), got (Math i64) at"));
+        assert!(issues[1]
+            .to_string()
+            .starts_with("Num expects (i64), got (Math Math) at"));
+        assert!(issues[2]
+            .to_string()
+            .starts_with("Num expects (i64), got (Math) at"));
+    }
+
+    #[test]
+    fn report_sort_mismatch_between_sides() {
+        let program_str = indoc! {"
+            (datatype Math (Num i64) (Add Math Math))
+            (datatype Bool (True) (False))
+            (ruleset math-rules)
+            (rule ((= e (Add x y))) ((union e (True))) :ruleset math-rules)
+            (rewrite (Add a b) (False) :ruleset math-rules)
+        "};
+        let (egglog_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(program_str).unwrap();
+        let issues = egglog_program.sort_issues();
+        assert_eq!(
+            2,
+            issues.len(),
+            "Both sides of a union and a rewrite should share a sort."
+        );
+        assert!(issues[0]
+            .to_string()
+            .starts_with("union expects (Math Math), got (Math Bool) at"));
+        assert!(issues[1]
+            .to_string()
+            .starts_with("rewrite expects (Math Math), got (Math Bool) at"));
+    }
+}
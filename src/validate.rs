@@ -0,0 +1,328 @@
+use std::fmt::Display;
+
+use egglog::ast::{
+    Action, Command, Expr, Fact, GenericAction, GenericExpr, GenericFact, Span, Symbol,
+};
+use itertools::Itertools;
+
+use crate::egraph::egglog_names::EGGLOG_PRIMITIVES;
+use crate::rules::declared_rulesets;
+use crate::schedule::visit_run_configs;
+use crate::{EgglogProgram, EgglogProgramError, EgglogSymbols};
+
+pub(crate) fn is_egglog_primitive(symbol: &Symbol) -> bool {
+    EGGLOG_PRIMITIVES.contains(&symbol.as_str())
+}
+
+// egglog registers more primitives with each builtin sort than
+// `EGGLOG_PRIMITIVES` lists, and all of them are lowercase or operators, so only
+// capitalized heads are reported as undeclared when they are not in the list.
+fn may_be_egglog_primitive(symbol: &Symbol) -> bool {
+    is_egglog_primitive(symbol) || !symbol.as_str().starts_with(char::is_uppercase)
+}
+
+pub(crate) fn expr_vars(expr: &Expr, vars: &mut EgglogSymbols) {
+    match expr {
+        GenericExpr::Lit(..) => {}
+        GenericExpr::Var(_span, var) => {
+            vars.insert(*var);
+        }
+        GenericExpr::Call(_span, _head, args) => args.iter().for_each(|arg| expr_vars(arg, vars)),
+    }
+}
+
+pub(crate) fn fact_exprs(fact: &Fact) -> Vec<&Expr> {
+    match fact {
+        GenericFact::Eq(_span, exprs) => exprs.iter().collect(),
+        GenericFact::Fact(expr) => vec![expr],
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ValidationIssue {
+    UndeclaredHead {
+        symbol: Symbol,
+        span: Span,
+    },
+    UnknownRuleset {
+        ruleset: Symbol,
+        span: Span,
+    },
+    UndeclaredVariable {
+        symbol: Symbol,
+        span: Span,
+    },
+    SortMismatch {
+        head: Symbol,
+        expected: Vec<Symbol>,
+        actual: Vec<Symbol>,
+        span: Span,
+    },
+}
+
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UndeclaredHead { symbol, span } => {
+                write!(f, "{}\nundeclared function or constructor {}", span, symbol)
+            }
+            Self::UnknownRuleset { ruleset, span } => {
+                write!(f, "{}\nunknown ruleset {}", span, ruleset)
+            }
+            Self::UndeclaredVariable { symbol, span } => {
+                write!(f, "{}\nundeclared variable {}", span, symbol)
+            }
+            Self::SortMismatch {
+                head,
+                expected,
+                actual,
+                span,
+            } => write!(
+                f,
+                "{} expects ({}), got ({}) at {}",
+                head,
+                expected.iter().join(" "),
+                actual.iter().join(" "),
+                span
+            ),
+        }
+    }
+}
+
+struct ProgramValidator<'a> {
+    declared: &'a EgglogSymbols,
+    rulesets: EgglogSymbols,
+    globals: EgglogSymbols,
+    issues: Vec<ValidationIssue>,
+}
+
+impl ProgramValidator<'_> {
+    fn check_head(&mut self, head: Symbol, span: &Span) {
+        if !self.declared.contains(&head) && !may_be_egglog_primitive(&head) {
+            self.issues.push(ValidationIssue::UndeclaredHead {
+                symbol: head,
+                span: span.clone(),
+            });
+        }
+    }
+
+    fn check_ruleset(&mut self, ruleset: Symbol, span: &Span) {
+        if !self.rulesets.contains(&ruleset) {
+            self.issues.push(ValidationIssue::UnknownRuleset {
+                ruleset,
+                span: span.clone(),
+            });
+        }
+    }
+
+    fn check_var(&mut self, var: Symbol, span: &Span, bound: &EgglogSymbols) {
+        if !bound.contains(&var) && !self.globals.contains(&var) && !self.declared.contains(&var) {
+            self.issues.push(ValidationIssue::UndeclaredVariable {
+                symbol: var,
+                span: span.clone(),
+            });
+        }
+    }
+
+    fn check_expr(&mut self, expr: &Expr, bound: Option<&EgglogSymbols>) {
+        match expr {
+            GenericExpr::Lit(..) => {}
+            GenericExpr::Var(span, var) => {
+                if let Some(bound) = bound {
+                    self.check_var(*var, span, bound);
+                }
+            }
+            GenericExpr::Call(span, head, args) => {
+                self.check_head(*head, span);
+                args.iter().for_each(|arg| self.check_expr(arg, bound));
+            }
+        }
+    }
+
+    fn check_query(&mut self, query: &[Fact], bound: &mut EgglogSymbols) {
+        for fact in query {
+            for expr in fact_exprs(fact) {
+                self.check_expr(expr, None);
+                expr_vars(expr, bound);
+            }
+        }
+    }
+
+    fn check_action(&mut self, action: &Action, bound: &mut EgglogSymbols) {
+        match action {
+            GenericAction::Let(_span, var, expr) => {
+                self.check_expr(expr, Some(bound));
+                bound.insert(*var);
+            }
+            GenericAction::Set(span, head, args, value) => {
+                self.check_head(*head, span);
+                args.iter()
+                    .for_each(|arg| self.check_expr(arg, Some(bound)));
+                self.check_expr(value, Some(bound));
+            }
+            GenericAction::Change(span, _change, head, args) => {
+                self.check_head(*head, span);
+                args.iter()
+                    .for_each(|arg| self.check_expr(arg, Some(bound)));
+            }
+            GenericAction::Union(_span, lhs, rhs) | GenericAction::Extract(_span, lhs, rhs) => {
+                self.check_expr(lhs, Some(bound));
+                self.check_expr(rhs, Some(bound));
+            }
+            GenericAction::Panic(..) => {}
+            GenericAction::Expr(_span, expr) => self.check_expr(expr, Some(bound)),
+        }
+    }
+
+    fn check_rule_command(&mut self, command: &Command) {
+        match command {
+            Command::Rule { ruleset, rule, .. } => {
+                self.check_ruleset(*ruleset, &rule.span);
+                let mut bound = EgglogSymbols::default();
+                self.check_query(&rule.body, &mut bound);
+                rule.head
+                    .0
+                    .iter()
+                    .for_each(|action| self.check_action(action, &mut bound));
+            }
+            Command::Rewrite(ruleset, rewrite, _subsume) => {
+                self.check_ruleset(*ruleset, &rewrite.span);
+                let mut bound = EgglogSymbols::default();
+                self.check_query(&rewrite.conditions, &mut bound);
+                self.check_expr(&rewrite.lhs, None);
+                expr_vars(&rewrite.lhs, &mut bound);
+                self.check_expr(&rewrite.rhs, Some(&bound));
+            }
+            Command::BiRewrite(ruleset, rewrite) => {
+                self.check_ruleset(*ruleset, &rewrite.span);
+                let mut condition_vars = EgglogSymbols::default();
+                self.check_query(&rewrite.conditions, &mut condition_vars);
+                let mut lhs_bound = condition_vars.clone();
+                expr_vars(&rewrite.lhs, &mut lhs_bound);
+                let mut rhs_bound = condition_vars;
+                expr_vars(&rewrite.rhs, &mut rhs_bound);
+                self.check_expr(&rewrite.lhs, Some(&rhs_bound));
+                self.check_expr(&rewrite.rhs, Some(&lhs_bound));
+            }
+            _ => {}
+        }
+    }
+
+    fn check_schedule_command(&mut self, command: &Command) {
+        if let Command::RunSchedule(schedule) = command {
+            visit_run_configs(schedule, &mut |span, run_config| {
+                self.check_ruleset(run_config.ruleset, span);
+                if let Some(until_facts) = &run_config.until {
+                    self.check_query(until_facts, &mut EgglogSymbols::default());
+                }
+            });
+        }
+    }
+}
+
+impl EgglogProgram {
+    pub fn validation_issues(&self) -> Vec<ValidationIssue> {
+        let mut validator = ProgramValidator {
+            declared: &self.sorts().0,
+            rulesets: declared_rulesets(self.rules().iter().flat_map(|rules| rules.iter())),
+            globals: EgglogSymbols::default(),
+            issues: vec![],
+        };
+        for command in self.facts().1.iter() {
+            if let Command::Action(action) = command {
+                let mut fact_bound = EgglogSymbols::default();
+                validator.check_action(action, &mut fact_bound);
+                validator.globals.extend(fact_bound);
+            }
+        }
+        for command in self.rules().iter().flat_map(|rules| rules.iter()) {
+            validator.check_rule_command(command);
+        }
+        for command in self
+            .schedules()
+            .iter()
+            .flat_map(|schedules| schedules.iter())
+        {
+            validator.check_schedule_command(command);
+        }
+        let mut issues = validator.issues;
+        issues.append(&mut self.sort_issues());
+        issues
+    }
+
+    pub fn validate(&self) -> Result<(), EgglogProgramError> {
+        let issues = self.validation_issues();
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(EgglogProgramError::Validation(issues))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn validate_well_formed_program() {
+        let program_str = indoc! {"
+            (datatype Math (Num i64) (Add Math Math))
+            (let expr1 (Add (Num 1) (Num 2)))
+            (let expr2 (Add expr1 (Num (+ 1 2))))
+            (let expr3 (Num (pow 2 (log2 8))))
+            (sort MathBag (MultiSet Math))
+            (let bag (multiset-of expr1 expr2))
+            (ruleset math-rules)
+            (rewrite (Add a b) (Add b a) :ruleset math-rules)
+            (rule ((= e (Add x (Num 0)))) ((union e x)) :ruleset math-rules)
+            (run-schedule (saturate (run math-rules)))
+        "};
+        let (egglog_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(program_str).unwrap();
+        assert!(
+            egglog_program.validate().is_ok(),
+            "Primitives of builtin sorts should resolve without a declaration."
+        );
+    }
+
+    #[test]
+    fn validate_reports_unresolved_symbols() {
+        let program_str = indoc! {"
+            (datatype Math (Num i64) (Add Math Math))
+            (let expr1 (Add (Num 1) expr0))
+            (ruleset math-rules)
+            (rewrite (Add a b) (Ad b a) :ruleset math-rules)
+            (rewrite (Add a b) (Add b c) :ruleset math-rules)
+            (run-schedule (saturate (run math-rule)))
+        "};
+        let (egglog_program, _unrouted_cmds) = EgglogProgram::from_egglog_str(program_str).unwrap();
+        let issues = egglog_program.validation_issues();
+        assert_eq!(
+            4,
+            issues.len(),
+            "Every unresolved symbol should be reported."
+        );
+        assert!(matches!(
+            issues[0],
+            ValidationIssue::UndeclaredVariable { symbol, .. } if symbol == Symbol::new("expr0")
+        ));
+        assert!(matches!(
+            issues[1],
+            ValidationIssue::UndeclaredHead { symbol, .. } if symbol == Symbol::new("Ad")
+        ));
+        assert!(matches!(
+            issues[2],
+            ValidationIssue::UndeclaredVariable { symbol, .. } if symbol == Symbol::new("c")
+        ));
+        assert!(matches!(
+            issues[3],
+            ValidationIssue::UnknownRuleset { ruleset, .. } if ruleset == Symbol::new("math-rule")
+        ));
+        assert!(matches!(
+            egglog_program.validate(),
+            Err(EgglogProgramError::Validation(..))
+        ));
+    }
+}